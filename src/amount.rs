@@ -0,0 +1,263 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::models::{Account, Money};
+
+#[derive(Debug, PartialEq)]
+pub enum AmountError {
+    Empty,
+    Invalid,
+    TooManyDecimals,
+    NotPositive,
+    TooLarge,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::Empty => write!(f, "Enter an amount"),
+            AmountError::Invalid => write!(f, "Not a valid amount, use e.g. 1 234,50"),
+            AmountError::TooManyDecimals => write!(f, "At most two decimals are allowed"),
+            AmountError::NotPositive => write!(f, "The amount must be greater than zero"),
+            AmountError::TooLarge => write!(f, "The amount is too large"),
+        }
+    }
+}
+
+// Parses a user-entered amount into an exact decimal with at most two decimals. Accepts Norwegian notation with a decimal comma
+// and space or dot thousands separators ("1 234,50", "1.234,50"), as well as a plain decimal point
+// ("1234.50"). A dot is only read as a decimal point when it is followed by one or two digits and
+// any commas come before it, as in English notation ("1,234.50").
+pub fn parse_amount(input: &str) -> Result<Decimal, AmountError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(AmountError::Empty);
+    }
+
+    if input.starts_with('-') {
+        return Err(AmountError::NotPositive);
+    }
+
+    let english = input
+        .rsplit_once('.')
+        .filter(|(int, frac)| int.contains(',') && !int.contains('.') && !frac.contains(','));
+
+    let (integer_part, fraction_part) = match (english, input.matches(',').count()) {
+        (Some((int, frac)), _) => (int, Some(frac)),
+        (None, 0) => match input.rsplit_once('.') {
+            Some((int, frac))
+                if input.matches('.').count() == 1 && (1..=2).contains(&frac.len()) =>
+            {
+                (int, Some(frac))
+            }
+            _ => (input, None),
+        },
+        (None, 1) => {
+            let (int, frac) = input.split_once(',').unwrap();
+            (int, Some(frac))
+        }
+        _ => return Err(AmountError::Invalid),
+    };
+
+    let integer_digits = parse_grouped_integer(integer_part)?;
+
+    let fraction_digits = match fraction_part {
+        None => "",
+        Some(frac) if frac.is_empty() || !frac.chars().all(|c| c.is_ascii_digit()) => {
+            return Err(AmountError::Invalid);
+        }
+        Some(frac) if frac.len() > 2 => return Err(AmountError::TooManyDecimals),
+        Some(frac) => frac,
+    };
+
+    let kroner: i64 = integer_digits.parse().map_err(|_| AmountError::TooLarge)?;
+    let ore: i64 = format!("{:0<2}", fraction_digits).parse().unwrap();
+
+    let minor_units = kroner
+        .checked_mul(100)
+        .and_then(|value| value.checked_add(ore))
+        .ok_or(AmountError::TooLarge)?;
+
+    if minor_units == 0 {
+        return Err(AmountError::NotPositive);
+    }
+
+    Ok(Decimal::new(minor_units, 2))
+}
+
+// Strips thousands separators, requiring every group after the first to have exactly three digits.
+fn parse_grouped_integer(input: &str) -> Result<String, AmountError> {
+    if input.is_empty() {
+        return Err(AmountError::Invalid);
+    }
+
+    let groups: Vec<&str> = input.split([' ', '\u{a0}', '.', ',']).collect();
+
+    let valid = groups.iter().enumerate().all(|(i, group)| {
+        let len_ok = if i == 0 {
+            !group.is_empty() && (groups.len() == 1 || group.len() <= 3)
+        } else {
+            group.len() == 3
+        };
+        len_ok && group.chars().all(|c| c.is_ascii_digit())
+    });
+
+    if !valid {
+        return Err(AmountError::Invalid);
+    }
+
+    Ok(groups.concat())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TransferError {
+    Amount(AmountError),
+    SameAccount,
+    FromNotAllowed(String),
+    ToNotAllowed(String),
+    InsufficientFunds(Money),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::Amount(err) => write!(f, "{}", err),
+            TransferError::SameAccount => write!(f, "Choose a different account to transfer to"),
+            TransferError::FromNotAllowed(name) => write!(f, "Transfers from {} are not allowed", name),
+            TransferError::ToNotAllowed(name) => write!(f, "Transfers to {} are not allowed", name),
+            TransferError::InsufficientFunds(available) => {
+                write!(f, "Exceeds the available balance of {}", available)
+            }
+        }
+    }
+}
+
+// Checks a transfer against the accounts involved before it is sent to the bank
+pub fn validate_transfer(
+    from: &Account,
+    to: &Account,
+    input: &str,
+) -> Result<Money, TransferError> {
+    if from.key == to.key {
+        return Err(TransferError::SameAccount);
+    }
+
+    if !from.account_properties.is_transfer_from_enabled {
+        return Err(TransferError::FromNotAllowed(from.name.clone()));
+    }
+
+    if !to.account_properties.is_transfer_to_enabled {
+        return Err(TransferError::ToNotAllowed(to.name.clone()));
+    }
+
+    let amount = parse_amount(input).map_err(TransferError::Amount)?;
+
+    if amount > from.available_balance {
+        return Err(TransferError::InsufficientFunds(from.available_money()));
+    }
+
+    Ok(Money::new(amount, &from.currency_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::AccountProperties;
+
+    fn account(key: &str, available: Decimal) -> Account {
+        Account {
+            key: key.to_string(),
+            name: key.to_string(),
+            available_balance: available,
+            currency_code: "NOK".to_string(),
+            account_properties: AccountProperties {
+                is_transfer_from_enabled: true,
+                is_transfer_to_enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_norwegian_notation() {
+        assert_eq!(parse_amount("1 000,50"), Ok(dec!(1000.50)));
+        assert_eq!(parse_amount("1.000,50"), Ok(dec!(1000.50)));
+        assert_eq!(parse_amount("1\u{a0}234 567,5"), Ok(dec!(1234567.50)));
+        assert_eq!(parse_amount("250"), Ok(dec!(250)));
+        assert_eq!(parse_amount("0,05"), Ok(dec!(0.05)));
+    }
+
+    #[test]
+    fn parses_english_notation() {
+        assert_eq!(parse_amount("1,000.50"), Ok(dec!(1000.50)));
+        assert_eq!(parse_amount("1,234,567.5"), Ok(dec!(1234567.50)));
+        assert_eq!(parse_amount("1234.50"), Ok(dec!(1234.50)));
+    }
+
+    #[test]
+    fn rejects_misplaced_separators() {
+        assert_eq!(parse_amount("1 00,50"), Err(AmountError::Invalid));
+        assert_eq!(parse_amount("1,000,50"), Err(AmountError::Invalid));
+        assert_eq!(parse_amount("1.000,000.50"), Err(AmountError::Invalid));
+        assert_eq!(parse_amount("12a"), Err(AmountError::Invalid));
+        assert_eq!(parse_amount("  "), Err(AmountError::Empty));
+    }
+
+    #[test]
+    fn rejects_more_than_two_decimals() {
+        assert_eq!(parse_amount("10,505"), Err(AmountError::TooManyDecimals));
+        assert_eq!(parse_amount("1,000.505"), Err(AmountError::TooManyDecimals));
+    }
+
+    #[test]
+    fn rejects_negative_and_zero_amounts() {
+        assert_eq!(parse_amount("-100"), Err(AmountError::NotPositive));
+        assert_eq!(parse_amount("0"), Err(AmountError::NotPositive));
+        assert_eq!(parse_amount("0,00"), Err(AmountError::NotPositive));
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        let err = parse_amount("999 999 999 999 999 999 999").unwrap_err();
+        assert_eq!(err, AmountError::TooLarge);
+        assert_eq!(err.to_string(), "The amount is too large");
+        assert_eq!(
+            parse_amount("92233720368547759"),
+            Err(AmountError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn rejects_transfer_to_same_account() {
+        let from = account("a", dec!(1000));
+        assert_eq!(
+            validate_transfer(&from, &from.clone(), "100"),
+            Err(TransferError::SameAccount)
+        );
+    }
+
+    #[test]
+    fn validates_transfer_against_available_balance() {
+        let from = account("a", dec!(100));
+        let to = account("b", dec!(0));
+        assert_eq!(
+            validate_transfer(&from, &to, "100,00"),
+            Ok(Money::new(dec!(100), "NOK"))
+        );
+        assert_eq!(
+            validate_transfer(&from, &to, "100,01"),
+            Err(TransferError::InsufficientFunds(Money::new(
+                dec!(100),
+                "NOK"
+            )))
+        );
+        assert_eq!(
+            validate_transfer(&from, &to, "0"),
+            Err(TransferError::Amount(AmountError::NotPositive))
+        );
+    }
+}
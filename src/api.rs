@@ -1,24 +1,35 @@
+use chrono::NaiveDate;
 use log::debug;
 use reqwest::{
-    Error,
-    blocking::{Client, Response},
+    StatusCode,
+    blocking::{Client, RequestBuilder, Response},
     header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue},
 };
-use tui_input::Input;
+use serde::de::DeserializeOwned;
 
 use crate::{
-    AppState, View, fileio::read_access_token_file, models::{AccountData, CreateTransferDTO, TransactionResponse, TransferResponse, TransferToCreditCardDTO}
+    auth::{self, Credentials},
+    error::ApiError,
+    models::{
+        AccountData, CreateTransferDTO, ErrorResponse, TransactionResponse, TransferResponse,
+        TransferToCreditCardDTO,
+    },
+    token_store,
 };
 
-fn client() -> Client {
-    let access_token = read_access_token_file().expect("Unable to read access token file!").access_token;
+// Returns the client along with the access token it sends, so a rejected token can be told apart
+// from one another request has already replaced
+fn client() -> Result<(Client, String), ApiError> {
+    let access_token = token_store::load()
+        .ok_or(ApiError::AuthExpired)?
+        .access_token;
 
     let mut headers = HeaderMap::new();
 
     headers.insert(
         AUTHORIZATION,
         HeaderValue::from_str(&format!("Bearer {}", access_token))
-            .expect("Invalid access token format"),
+            .map_err(|_| ApiError::AuthExpired)?,
     );
 
     headers.insert(
@@ -26,212 +37,120 @@ fn client() -> Client {
         HeaderValue::from_static("application/vnd.sparebank1.v1+json; charset=utf-8"),
     );
 
-    Client::builder()
-        .default_headers(headers)
-        .build()
-        .expect("Unable to create API client")
+    Ok((
+        Client::builder().default_headers(headers).build()?,
+        access_token,
+    ))
 }
 
-pub fn get_accounts() -> AccountData {
-
-    let account_response = client()
-        .get("https://api.sparebank1.no/personal/banking/accounts?includeCreditCardAccounts=true")
-        .send();
-
-    let data: AccountData = match account_response {
-        Ok(response) => {
-            let text = response
-                .text()
-                .expect("Failed to get account response text");
-            serde_json::from_str(&text).expect("Failed to parse accounts JSON")
-        }
-        Err(err) => {
-            panic!("Paniced: {}", err)
-        }
-    };
-
-    data
+// Sends the request built by `request`. An expired token is refreshed up front, and if the bank
+// still answers 401 the token is refreshed and the request sent once more.
+fn send(
+    credentials: &Credentials,
+    request: impl Fn(&Client) -> RequestBuilder,
+) -> Result<Response, ApiError> {
+    if let Some(token_data) = token_store::load()
+        && !token_data.is_access_valid(chrono::Utc::now().timestamp())
+    {
+        debug!("Access token expired, refreshing before request");
+        auth::refresh_saved_token(credentials, &token_data.access_token)?;
+    }
+
+    let (http, access_token) = client()?;
+    let response = request(&http).send()?;
+    if response.status() != StatusCode::UNAUTHORIZED {
+        return Ok(response);
+    }
+
+    debug!("Request was rejected with 401, refreshing token and retrying");
+    auth::refresh_saved_token(credentials, &access_token)?;
+    let (http, _) = client()?;
+    Ok(request(&http).send()?)
 }
 
-pub fn get_transactions(account_key: &String) -> TransactionResponse {
-    let url = format!(
-        "https://api.sparebank1.no/personal/banking/transactions?accountKey={}",
-        account_key
-    );
+// Turns a response into `T`, mapping 401 to `AuthExpired` and any other non-2xx status to
+// `Http` with whatever ErrorDTOs the bank sent along.
+fn read_json<T: DeserializeOwned>(response: Response) -> Result<T, ApiError> {
+    let status = response.status();
+    let text = response.text()?;
+
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(ApiError::AuthExpired);
+    }
+
+    if !status.is_success() {
+        debug!("API returned HTTP {}: {}", status, text);
+        let errors = serde_json::from_str::<ErrorResponse>(&text)
+            .map(|body| body.errors)
+            .unwrap_or_default();
+        return Err(ApiError::Http {
+            status: status.as_u16(),
+            errors,
+        });
+    }
 
-    let transactions_respose = client().get(&url).send();
-
-    let data: TransactionResponse = match transactions_respose {
-        Ok(response) => {
-            let text = response
-                .text()
-                .expect("Failed to get transactions response text");
-            serde_json::from_str(&text).expect("Failed to parse transactions JSON")
-        }
-        Err(err) => {
-            panic!("Shieeet: {}", err)
-        }
-    };
-    data
+    serde_json::from_str(&text).map_err(|err| {
+        debug!("Failed to parse JSON: {}\nResponse was: {}", err, text);
+        ApiError::Decode(err)
+    })
 }
 
-pub fn hello_world() -> Result<Response, Error> {
-    client()
-        .get("https://api.sparebank1.no/common/helloworld")
-        .send()
+pub fn get_accounts(base_url: &str, credentials: &Credentials) -> Result<AccountData, ApiError> {
+    let url = format!(
+        "{}/personal/banking/accounts?includeCreditCardAccounts=true",
+        base_url
+    );
+
+    read_json(send(credentials, |client| client.get(&url))?)
 }
 
-pub fn perform_transfer(app: &mut AppState) {
-    let amount = app.amount_input.value().trim();
-    if amount.is_empty() {
-        debug!("Amount is empty, not performing transfer");
-        return;
+pub fn get_transactions(
+    base_url: &str,
+    credentials: &Credentials,
+    account_key: &str,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    row_limit: usize,
+) -> Result<TransactionResponse, ApiError> {
+    let url = format!("{}/personal/banking/transactions", base_url);
+
+    let mut query = vec![
+        ("accountKey", account_key.to_string()),
+        ("rowLimit", row_limit.to_string()),
+    ];
+    if let Some(from) = from {
+        query.push(("fromDate", from.format("%Y-%m-%d").to_string()));
     }
-
-    let from_account = match app.from_account {
-        Some(idx) => &app.accounts[idx],
-        None => {
-            debug!("No from_account selected");
-            return;
-        }
-    };
-
-    let to_account = match app.to_account {
-        Some(idx) => &app.accounts[idx],
-        None => {
-            debug!("No to_account selected");
-            return;
-        }
-    };
-
-    let is_credit_card = to_account.type_field == "CREDITCARD";
-
-    let response = if is_credit_card {
-        // Credit card transfer - does not support message field
-        let credit_card_id = to_account
-            .credit_card_account_id
-            .as_ref()
-            .expect("Credit card account missing credit_card_account_id");
-
-        let transfer = TransferToCreditCardDTO {
-            amount: amount.to_string(),
-            due_date: None,
-            from_account: from_account.account_number.clone(),
-            credit_card_account_id: credit_card_id.clone(),
-        };
-
-        debug!("Performing credit card transfer: {:?}", transfer);
-        create_credit_card_transfer(transfer)
-    } else {
-        let message = app.message_input.value().trim();
-        let message = if message.is_empty() {
-            None
-        } else {
-            Some(message.to_string())
-        };
-
-        let transfer = CreateTransferDTO {
-            amount: amount.to_string(),
-            due_date: None,
-            message,
-            to_account: to_account.account_number.clone(),
-            from_account: from_account.account_number.clone(),
-            currency_code: None,
-        };
-
-        debug!("Performing transfer: {:?}", transfer);
-        create_transfer(transfer)
-    };
-
-    if response.errors.is_empty() {
-        debug!("Transfer successful! Payment ID: {:?}", response.payment_id);
-
-        // Reset state
-        app.amount_input = Input::default();
-        app.message_input = Input::default();
-        app.from_account = None;
-        app.to_account = None;
-
-        app.view_stack.clear();
-        app.view_stack.push(View::Accounts);
-
-        app.accounts = crate::get_accounts();
-    } else {
-        debug!("Transfer failed with {} error(s):", response.errors.len());
-        for error in &response.errors {
-            debug!(
-                "  - [{}] {} (HTTP {}): {}",
-                error.code, error.trace_id, error.http_code, error.message
-            );
-            if let Some(localized) = &error.localized_message
-                && let Some(msg) = &localized.message {
-                    debug!("    Localized: {}", msg);
-                }
-        }
+    if let Some(to) = to {
+        query.push(("toDate", to.format("%Y-%m-%d").to_string()));
     }
+
+    read_json(send(credentials, |client| client.get(&url).query(&query))?)
 }
 
+pub fn create_transfer(
+    base_url: &str,
+    credentials: &Credentials,
+    transfer: CreateTransferDTO,
+) -> Result<TransferResponse, ApiError> {
+    let url = format!("{}/personal/banking/transfer/debit", base_url);
 
-pub fn create_transfer(transfer: CreateTransferDTO) -> TransferResponse {
-    let url = "https://api.sparebank1.no/personal/banking/transfer/debit";
-
-    let transfer_response = client().post(url).json(&transfer).send();
-
-    let data: TransferResponse = match transfer_response {
-        Ok(response) => {
-            let status = response.status();
-            let text = response
-                .text()
-                .expect("Failed to get transfer response text");
-
-            if !status.is_success() {
-                panic!(
-                    "Transfer API returned HTTP {}: {}",
-                    status,
-                    text
-                );
-            }
-
-            serde_json::from_str(&text).unwrap_or_else(|err| {
-                panic!("Failed to parse transfer JSON: {}\nResponse was: {}", err, text)
-            })
-        }
-        Err(err) => {
-            panic!("Transfer request failed: {}", err)
-        }
-    };
-
-    data
+    read_json(send(credentials, |client| {
+        client.post(&url).json(&transfer)
+    })?)
 }
 
-pub fn create_credit_card_transfer(transfer: TransferToCreditCardDTO) -> TransferResponse {
-    let url = "https://api.sparebank1.no/personal/banking/transfer/creditcard/transferTo";
-
-    let transfer_response = client().post(url).json(&transfer).send();
-
-    let data: TransferResponse = match transfer_response {
-        Ok(response) => {
-            let status = response.status();
-            let text = response
-                .text()
-                .expect("Failed to get credit card transfer response text");
-
-            if !status.is_success() {
-                panic!(
-                    "Credit card transfer API returned HTTP {}: {}",
-                    status,
-                    text
-                );
-            }
-
-            serde_json::from_str(&text).unwrap_or_else(|err| {
-                panic!("Failed to parse credit card transfer JSON: {}\nResponse was: {}", err, text)
-            })
-        }
-        Err(err) => {
-            panic!("Credit card transfer request failed: {}", err)
-        }
-    };
-
-    data
+pub fn create_credit_card_transfer(
+    base_url: &str,
+    credentials: &Credentials,
+    transfer: TransferToCreditCardDTO,
+) -> Result<TransferResponse, ApiError> {
+    let url = format!(
+        "{}/personal/banking/transfer/creditcard/transferTo",
+        base_url
+    );
+
+    read_json(send(credentials, |client| {
+        client.post(&url).json(&transfer)
+    })?)
 }
@@ -1,101 +1,289 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use log::debug;
 use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::sync::mpsc;
+use std::io::{self, Write};
+use std::sync::{Mutex, mpsc};
 use tiny_http::{Response, Server};
 use url::form_urlencoded;
 use urlencoding::encode;
 
-use crate::api;
-use crate::fileio::{read_access_token_file, save_token_data_file};
+use crate::error::ApiError;
+use crate::fileio::AppConfig;
 use crate::models::TokenData;
+use crate::token_store;
 
-pub fn auth(client_id: String, client_secret: String, financial_institution: String) {
-    if let Some(token_data) = read_access_token_file() {
-        if is_token_valid() {
-            return;
+// Worker threads can hit an expired token at the same time, and the bank rotates the refresh token,
+// so only one of them may refresh
+static REFRESH_LOCK: Mutex<()> = Mutex::new(());
+
+// What the API layer needs to refresh the token by itself in the middle of a session
+#[derive(Clone)]
+pub struct Credentials {
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+impl Credentials {
+    pub fn new(config: &AppConfig) -> Self {
+        Self {
+            token_url: config.token_url(),
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
         }
+    }
+}
+
+// Refreshes and saves the token, unless another thread already replaced `rejected_token` while
+// this one waited for the lock.
+pub fn refresh_saved_token(
+    credentials: &Credentials,
+    rejected_token: &str,
+) -> Result<(), ApiError> {
+    let _guard = REFRESH_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let token_data = token_store::reload().ok_or(ApiError::AuthExpired)?;
+    if token_data.access_token != rejected_token {
+        debug!("Token was already refreshed by another request");
+        return Ok(());
+    }
 
-        debug!("Access token not valid, attempting to refresh...");
+    let new_token_data = refresh_access_token(
+        &credentials.token_url,
+        &credentials.client_id,
+        &credentials.client_secret,
+        &token_data.refresh_token,
+    )
+    .inspect_err(|err| debug!("Token refresh failed: {}", err))?;
 
-        if let Ok(new_token_data) =
-            refresh_access_token(&client_id, &client_secret, &token_data.refresh_token)
-        {
-            save_token_data_file(&new_token_data);
-            debug!("Token refreshed successfully");
-            return;
+    if let Err(err) = token_store::save(&new_token_data) {
+        debug!("Refreshed token could not be saved: {}", err);
+    }
+    debug!("Token refreshed successfully");
+    Ok(())
+}
+
+pub fn auth(config: &AppConfig, headless: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let client_id = &config.client_id;
+    let client_secret = &config.client_secret;
+    let token_url = config.token_url();
+
+    // Expiry is worked out from when the token was issued, so no request is needed to check it
+    if let Some(token_data) = token_store::load() {
+        let now = chrono::Utc::now().timestamp();
+
+        if token_data.is_access_valid(now) {
+            debug!(
+                "Access token valid until {:?}",
+                token_data.access_expires_at()
+            );
+            return Ok(());
+        }
+
+        if token_data.is_refresh_valid(now) {
+            debug!("Access token expired or about to expire, refreshing...");
+            match refresh_access_token(
+                &token_url,
+                client_id,
+                client_secret,
+                &token_data.refresh_token,
+            ) {
+                Ok(new_token_data) => {
+                    token_store::save(&new_token_data)?;
+                    debug!("Token refreshed successfully");
+                    return Ok(());
+                }
+                Err(ApiError::AuthExpired) => debug!("Refresh token was rejected"),
+                // Without the bank there is nothing to log in to, but the saved accounts and
+                // transactions can still be shown. The token is refreshed again on the first request.
+                Err(err) => {
+                    debug!("Token refresh failed, continuing offline: {}", err);
+                    return Ok(());
+                }
+            }
+        } else {
+            debug!(
+                "Refresh token expired at {:?}",
+                token_data.refresh_expires_at()
+            );
         }
     }
 
-    debug!("Token refresh failed, starting full OAuth flow...");
-    let code = get_code(&client_id, &financial_institution);
-    if let Ok(token_data) = get_access_token(&code, &client_id, &client_secret) {
-        save_token_data_file(&token_data);
-        debug!("Access token obtained and saved successfully");
+    debug!("Starting full OAuth flow...");
+    let code_verifier = random_token()?;
+    let code = get_code(config, &code_verifier, headless)
+        .map_err(|err| format!("Login failed: {}", err))?;
+    let token_data = get_access_token(
+        &token_url,
+        &code,
+        &code_verifier,
+        &config.redirect_uri(),
+        client_id,
+        client_secret,
+    )?;
+    token_store::save(&token_data)?;
+    debug!("Access token obtained and saved successfully");
+    Ok(())
+}
+
+// 32 random bytes, base64url encoded. Long enough for both the state and a PKCE code verifier.
+fn random_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn get_code(config: &AppConfig, code_verifier: &str, headless: bool) -> Result<String, String> {
+    let redirect_uri = config.redirect_uri();
+    let state = random_token().map_err(|err| err.to_string())?;
+
+    let auth_url = format!(
+        "{}?client_id={}&state={}&redirect_uri={}&finInst={}&response_type=code&code_challenge={}&code_challenge_method=S256",
+        config.authorize_url(),
+        config.client_id,
+        state,
+        encode(&redirect_uri),
+        config.financial_institution,
+        code_challenge(code_verifier)
+    );
+
+    if headless {
+        paste_code(&auth_url, &state)
     } else {
-        panic!("Failed to obtain access token from OAuth flow");
+        wait_for_callback(config, &auth_url, &state)
     }
 }
 
-fn get_code(client_id: &str, financial_institution: &str) -> String {
-    let port = 8321;
-    let redirect_uri = format!("http://localhost:{port}");
-
-    let server = Server::http(format!("127.0.0.1:{port}")).unwrap();
+// Waits for the bank to redirect the browser back to the local server
+fn wait_for_callback(config: &AppConfig, auth_url: &str, state: &str) -> Result<String, String> {
+    let address = format!("{}:{}", config.callback_address, config.callback_port);
+    let server = Server::http(&address)
+        .map_err(|err| format!("could not listen on {}: {}", address, err))?;
 
     let (tx, rx) = mpsc::channel();
 
+    let expected_state = state.to_string();
     std::thread::spawn(move || {
         for request in server.incoming_requests() {
             let query = request.url().split('?').nth(1).unwrap_or("");
-            let params: HashMap<_, _> = form_urlencoded::parse(query.as_bytes())
-                .into_owned()
-                .collect();
+            let params = parse_query(query);
 
-            if let Some(code) = params.get("code").cloned() {
-                let response =
-                    Response::from_string("✅ Authentication complete! You can close this tab.");
-                request.respond(response).unwrap();
-
-                tx.send(code).unwrap();
-                break; // exit server loop
+            // Browsers also ask for /favicon.ico and the like
+            if !params.contains_key("code") && !params.contains_key("error") {
+                let _ = request.respond(Response::from_string("Not found").with_status_code(404));
+                continue;
             }
+
+            let result = check_callback(&params, &expected_state);
+            let response = match &result {
+                Ok(_) => {
+                    Response::from_string("✅ Authentication complete! You can close this tab.")
+                }
+                Err(err) => Response::from_string(format!(
+                    "❌ Authentication failed: {}.\n\nClose this tab and try again from auox.",
+                    err
+                ))
+                .with_status_code(400),
+            };
+            let _ = request.respond(response);
+
+            let _ = tx.send(result);
+            break; // exit server loop
         }
     });
 
-    let auth_url = format!(
-        "https://api.sparebank1.no/oauth/authorize?client_id={}&state=123&redirect_uri={}&finInst={}&response_type=code",
-        client_id,
-        encode(&redirect_uri),
-        financial_institution
-    );
-    open::that(&auth_url).unwrap();
+    // No browser to open, e.g. over SSH with the callback port forwarded
+    if let Err(err) = open::that(auth_url) {
+        debug!("Could not open a browser: {}", err);
+        eprintln!("Open this URL in a browser to log in:\n\n{auth_url}\n");
+    }
+
+    eprintln!("Waiting for OAuth callback on {address}...");
+
+    rx.recv().map_err(|err| err.to_string())?
+}
+
+// For machines the browser can't reach. The redirect to localhost fails to load, but its URL
+// in the address bar carries the code.
+fn paste_code(auth_url: &str, state: &str) -> Result<String, String> {
+    eprintln!("Open this URL in a browser to log in:\n\n{auth_url}\n");
+    eprintln!("After logging in, the browser is sent to a localhost page that won't load.");
+    eprint!("Paste that page's URL from the address bar (or just the code): ");
+    io::stderr().flush().map_err(|err| err.to_string())?;
+
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|err| err.to_string())?;
+    let input = line.trim();
+
+    // A bare code can't be checked against the state, but it can only come from the user
+    match input.split_once('?') {
+        _ if input.is_empty() => Err("nothing was pasted".to_string()),
+        Some((_, query)) => check_callback(&parse_query(query), state),
+        None if input.contains('=') => check_callback(&parse_query(input), state),
+        None => Ok(input.to_string()),
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
 
-    println!("Waiting for OAuth callback on {redirect_uri}...");
+// The callback must carry the state sent with this login, and an `error` from the bank ends the
+// login
+fn check_callback(
+    params: &HashMap<String, String>,
+    expected_state: &str,
+) -> Result<String, String> {
+    if params.get("state").map(String::as_str) != Some(expected_state) {
+        return Err("the callback did not come from this login (state mismatch)".to_string());
+    }
+
+    if let Some(error) = params.get("error") {
+        return match params.get("error_description") {
+            Some(description) => Err(format!("the bank returned {}: {}", error, description)),
+            None => Err(format!("the bank returned {}", error)),
+        };
+    }
 
-    let code = rx.recv().unwrap();
-    println!("Code: {}", code);
-    code
+    params
+        .get("code")
+        .cloned()
+        .ok_or_else(|| "the callback had no code".to_string())
 }
 
 fn get_access_token(
+    token_url: &str,
     code: &str,
+    code_verifier: &str,
+    redirect_uri: &str,
     client_id: &str,
     client_secret: &str,
 ) -> Result<TokenData, Box<dyn std::error::Error>> {
     let client = Client::new();
-    let redirect_uri = "http://localhost:8321";
 
     let params = [
         ("client_id", client_id),
         ("client_secret", client_secret),
         ("code", code),
+        ("code_verifier", code_verifier),
         ("grant_type", "authorization_code"),
         ("redirect_uri", redirect_uri),
     ];
 
     let response = client
-        .post("https://api.sparebank1.no/oauth/token")
+        .post(token_url)
         .header("Content-Type", "application/x-www-form-urlencoded")
         .form(&params)
         .send()?;
@@ -103,19 +291,26 @@ fn get_access_token(
     if !response.status().is_success() {
         let status = response.status();
         let error_body = response.text().unwrap_or_default();
-        return Err(format!("Token exchange failed with status {}: {}", status, error_body).into());
+        return Err(format!(
+            "Token exchange failed with status {}: {}",
+            status, error_body
+        )
+        .into());
     }
 
     let token_data: TokenData = response.json()?;
 
-    Ok(token_data)
+    Ok(token_data.issued_now())
 }
 
+// A refresh token the bank turns down gives `AuthExpired`. Network and server errors are kept
+// apart, since logging in again won't help with those.
 fn refresh_access_token(
+    token_url: &str,
     client_id: &str,
     client_secret: &str,
     refresh_token: &str,
-) -> Result<TokenData, Box<dyn std::error::Error>> {
+) -> Result<TokenData, ApiError> {
     let client = Client::new();
 
     let params = [
@@ -126,25 +321,24 @@ fn refresh_access_token(
     ];
 
     let response = client
-        .post("https://api.sparebank1.no/oauth/token")
+        .post(token_url)
         .header("Content-Type", "application/x-www-form-urlencoded")
         .form(&params)
         .send()?;
 
-    if !response.status().is_success() {
-        return Err(format!("Token refresh failed with status: {}", response.status()).into());
+    let status = response.status();
+    if status.is_client_error() {
+        debug!("Token refresh failed with status: {}", status);
+        return Err(ApiError::AuthExpired);
+    }
+    if !status.is_success() {
+        return Err(ApiError::Http {
+            status: status.as_u16(),
+            errors: Vec::new(),
+        });
     }
 
     let token_data: TokenData = response.json()?;
 
-    Ok(token_data)
-}
-
-fn is_token_valid() -> bool {
-    let response = api::hello_world();
-
-    match response {
-        Ok(resp) => resp.status().is_success(),
-        Err(_) => false,
-    }
+    Ok(token_data.issued_now())
 }
@@ -0,0 +1,417 @@
+use std::sync::Mutex;
+
+use log::debug;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::{
+    bank::{booking_date, BankProvider, DateRange},
+    error::ApiError,
+    models::{
+        Account, AccountNumber, AccountProperties, ClassificationInput, ErrorDTO,
+        LocalizedMessage, Owner, Transaction, TransferResponse,
+    },
+};
+
+// 2025-10-01 00:00 UTC, so demos and screenshots look the same on every run.
+const BASE_DATE_MS: i64 = 1_759_276_800_000;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const CREDIT_LIMIT: Decimal = dec!(20000);
+// Like the real API, only recent transactions are returned unless asked for a date range
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+
+// Offline stand-in for a real bank. Serves fixed accounts and transactions, and keeps balances and
+// history consistent when transfers are made. With `fail_transfers` every transfer is rejected.
+pub struct MockBank {
+    state: Mutex<MockState>,
+    fail_transfers: bool,
+}
+
+struct MockState {
+    accounts: Vec<Account>,
+    transactions: Vec<Transaction>,
+    next_id: u64,
+}
+
+impl MockBank {
+    pub fn new(fail_transfers: bool) -> Self {
+        let accounts = vec![
+            account("1", "1234.56.78901", "Brukskonto", "CURRENT", dec!(12345.67), None),
+            account("2", "1234.56.78902", "Sparekonto", "SAVINGS", dec!(85000.00), None),
+            account("3", "1234.56.78903", "Regningskonto", "CURRENT", dec!(4210.50), None),
+            account("4", "5432.10.98765", "Mastercard", "CREDITCARD", dec!(-3456.78), Some("cc-4")),
+        ];
+
+        let mut transactions = Vec::new();
+        let mut next_id = 1;
+        for (days_ago, account_idx, description, amount, type_text, remote) in SEED_TRANSACTIONS {
+            transactions.push(transaction(
+                &accounts[*account_idx],
+                next_id,
+                BASE_DATE_MS - days_ago * DAY_MS,
+                description,
+                *amount,
+                type_text,
+                *remote,
+            ));
+            next_id += 1;
+        }
+
+        Self {
+            state: Mutex::new(MockState {
+                accounts,
+                transactions,
+                next_id,
+            }),
+            fail_transfers,
+        }
+    }
+}
+
+// (days before BASE_DATE_MS, account index, description, amount, type, remote account name)
+type Seed = (i64, usize, &'static str, Decimal, &'static str, Option<&'static str>);
+
+const SEED_TRANSACTIONS: &[Seed] = &[
+    (1, 0, "REMA 1000 TRONDHEIM", dec!(-389.40), "Varekjøp", None),
+    (2, 0, "VINMONOPOLET SOLSIDEN", dec!(-249.90), "Varekjøp", None),
+    (3, 0, "Lønn", dec!(38500.00), "Lønn", Some("ARBEIDSGIVER AS")),
+    (4, 0, "SPOTIFY", dec!(-129.00), "Varekjøp", None),
+    (6, 0, "Husleie", dec!(-12000.00), "Nettbank til", Some("UTLEIER AS")),
+    (9, 0, "KIWI BAKKLANDET", dec!(-212.35), "Varekjøp", None),
+    (12, 0, "Vipps *Kari Nordmann", dec!(450.00), "Vipps", Some("Kari Nordmann")),
+    (15, 0, "ATB AS", dec!(-890.00), "Varekjøp", None),
+    (3, 1, "Fast overføring", dec!(2000.00), "Overføring", Some("Brukskonto")),
+    (31, 1, "Renter", dec!(312.44), "Renter", None),
+    (33, 1, "Fast overføring", dec!(2000.00), "Overføring", Some("Brukskonto")),
+    (5, 2, "Strøm", dec!(-1245.10), "Avtalegiro", Some("TRONDHEIM ENERGI")),
+    (10, 2, "Forsikring", dec!(-689.00), "Avtalegiro", Some("FORSIKRING AS")),
+    (2, 3, "NETFLIX.COM", dec!(-179.00), "Varekjøp", None),
+    (8, 3, "ELKJOP TRONDHEIM", dec!(-2999.00), "Varekjøp", None),
+    (34, 0, "Lønn", dec!(38500.00), "Lønn", Some("ARBEIDSGIVER AS")),
+    (37, 0, "Husleie", dec!(-12000.00), "Nettbank til", Some("UTLEIER AS")),
+    (41, 0, "REMA 1000 TRONDHEIM", dec!(-512.80), "Varekjøp", None),
+    (48, 0, "COOP MEGA MOHOLT", dec!(-734.20), "Varekjøp", None),
+    (64, 0, "Lønn", dec!(38500.00), "Lønn", Some("ARBEIDSGIVER AS")),
+    (67, 0, "Husleie", dec!(-12000.00), "Nettbank til", Some("UTLEIER AS")),
+    (80, 0, "VY", dec!(-649.00), "Varekjøp", None),
+];
+
+// (remote account name, account number, KID) so the detail view has something to show
+const REMOTE_DETAILS: &[(&str, &str, Option<&str>)] = &[
+    ("ARBEIDSGIVER AS", "8601.11.22334", None),
+    ("UTLEIER AS", "1503.44.55667", Some("Husleie oktober")),
+    ("Kari Nordmann", "1234.98.76543", None),
+    ("Brukskonto", "1234.56.78901", None),
+    ("TRONDHEIM ENERGI", "4200.12.34567", Some("0012345678903")),
+    ("FORSIKRING AS", "7058.65.43210", Some("2024091500017")),
+];
+
+impl BankProvider for MockBank {
+    fn accounts(&self) -> Result<Vec<Account>, ApiError> {
+        Ok(self.state.lock().unwrap().accounts.clone())
+    }
+
+    fn transactions(
+        &self,
+        account_key: &str,
+        range: &DateRange,
+    ) -> Result<Vec<Transaction>, ApiError> {
+        let range = match range {
+            DateRange { from: None, to: None } => DateRange {
+                from: Some(booking_date(BASE_DATE_MS - DEFAULT_WINDOW_DAYS * DAY_MS)),
+                to: None,
+            },
+            range => *range,
+        };
+
+        let mut transactions: Vec<Transaction> = self
+            .state
+            .lock()
+            .unwrap()
+            .transactions
+            .iter()
+            .filter(|tx| tx.account_key == account_key)
+            .filter(|tx| range.contains(tx.date))
+            .cloned()
+            .collect();
+        transactions.sort_by_key(|tx| std::cmp::Reverse(tx.date));
+        Ok(transactions)
+    }
+
+    fn transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: Decimal,
+        message: Option<String>,
+    ) -> Result<TransferResponse, ApiError> {
+        if self.fail_transfers {
+            return rejected("mock.simulated_failure", "Simulert feil fra mock-banken");
+        }
+
+        if amount <= Decimal::ZERO {
+            return rejected("transfer.invalid_amount", "Ugyldig beløp");
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let Some(from_idx) = state.accounts.iter().position(|a| a.key == from.key) else {
+            return rejected("transfer.unknown_account", "Ukjent fra-konto");
+        };
+        let Some(to_idx) = state.accounts.iter().position(|a| a.key == to.key) else {
+            return rejected("transfer.unknown_account", "Ukjent til-konto");
+        };
+
+        if state.accounts[from_idx].available_balance < amount {
+            return rejected("transfer.insufficient_funds", "Ikke nok disponibelt beløp");
+        }
+
+        state.accounts[from_idx].balance -= amount;
+        state.accounts[from_idx].available_balance -= amount;
+        state.accounts[to_idx].balance += amount;
+        state.accounts[to_idx].available_balance += amount;
+
+        let description = message.unwrap_or_else(|| "Overføring".to_string());
+        let date = BASE_DATE_MS + state.next_id as i64 * 60_000;
+        let from_account = state.accounts[from_idx].clone();
+        let to_account = state.accounts[to_idx].clone();
+
+        let id = state.next_id;
+        let mut debit = transaction(
+            &from_account,
+            id,
+            date,
+            &description,
+            -amount,
+            "Overføring",
+            Some(&to_account.name),
+        );
+        let mut credit = transaction(
+            &to_account,
+            id + 1,
+            date,
+            &description,
+            amount,
+            "Overføring",
+            Some(&from_account.name),
+        );
+        debit.remote_account_number = Some(to_account.account_number.clone());
+        credit.remote_account_number = Some(from_account.account_number.clone());
+        state.transactions.push(debit);
+        state.transactions.push(credit);
+        state.next_id += 2;
+
+        debug!("Mock transfer of {} from {} to {}", amount, from.name, to.name);
+
+        Ok(TransferResponse {
+            errors: vec![],
+            payment_id: Some(format!("mock-payment-{}", id)),
+            status: Some("PROCESSED".to_string()),
+        })
+    }
+}
+
+fn rejected(code: &str, message: &str) -> Result<TransferResponse, ApiError> {
+    Err(ApiError::Http {
+        status: 422,
+        errors: vec![ErrorDTO {
+            code: code.to_string(),
+            message: message.to_string(),
+            trace_id: "mock-trace".to_string(),
+            http_code: 422,
+            resource: None,
+            localized_message: Some(LocalizedMessage {
+                locale: Some("nb".to_string()),
+                message: Some(message.to_string()),
+            }),
+        }],
+    })
+}
+
+fn account(
+    key: &str,
+    account_number: &str,
+    name: &str,
+    type_field: &str,
+    balance: Decimal,
+    credit_card_account_id: Option<&str>,
+) -> Account {
+    let is_credit_card = type_field == "CREDITCARD";
+
+    Account {
+        key: key.to_string(),
+        account_number: account_number.to_string(),
+        iban: format!("NO93{}", account_number.replace('.', "")),
+        name: name.to_string(),
+        description: name.to_string(),
+        balance,
+        available_balance: if is_credit_card { balance + CREDIT_LIMIT } else { balance },
+        currency_code: "NOK".to_string(),
+        owner: Some(Owner {
+            name: "Ola Nordmann".to_string(),
+            first_name: "Ola".to_string(),
+            last_name: "Nordmann".to_string(),
+            age: 42,
+            customer_key: "mock-customer".to_string(),
+            ssn_key: "mock-ssn".to_string(),
+        }),
+        product_type: type_field.to_string(),
+        type_field: type_field.to_string(),
+        product_id: None,
+        description_code: None,
+        account_properties: AccountProperties {
+            is_transfer_from_enabled: !is_credit_card,
+            is_transfer_to_enabled: true,
+            is_payment_from_enabled: !is_credit_card,
+            has_access: true,
+            is_savings_account: type_field == "SAVINGS",
+            user_has_right_of_disposal: true,
+            user_has_right_of_access: true,
+            is_owned: true,
+            is_withdrawals_allowed: true,
+            ..Default::default()
+        },
+        credit_card_credit_limit: is_credit_card.then_some(CREDIT_LIMIT),
+        credit_card_account_id: credit_card_account_id.map(str::to_string),
+    }
+}
+
+fn transaction(
+    account: &Account,
+    id: u64,
+    date: i64,
+    description: &str,
+    amount: Decimal,
+    type_text: &str,
+    remote_account_name: Option<&str>,
+) -> Transaction {
+    let id = format!("mock-tx-{}", id);
+    let remote = remote_account_name
+        .and_then(|name| REMOTE_DETAILS.iter().find(|(remote, _, _)| *remote == name));
+
+    Transaction {
+        id: id.clone(),
+        non_unique_id: id.clone(),
+        description: Some(description.to_string()),
+        cleaned_description: Some(description.to_string()),
+        account_number: AccountNumber {
+            value: account.account_number.replace('.', ""),
+            formatted: account.account_number.clone(),
+            unformatted: account.account_number.replace('.', ""),
+        },
+        amount,
+        date,
+        interest_date: Some(date),
+        type_code: type_text.to_uppercase(),
+        type_text: type_text.to_string(),
+        currency_code: account.currency_code.clone(),
+        can_show_details: true,
+        source: "MOCK".to_string(),
+        is_confidential: false,
+        booking_status: "BOOKED".to_string(),
+        account_name: account.name.clone(),
+        account_key: account.key.clone(),
+        account_currency: account.currency_code.clone(),
+        is_from_currency_account: false,
+        classification_input: ClassificationInput {
+            id: id.clone(),
+            amount,
+            type_field: type_text.to_string(),
+            text: Some(description.to_string()),
+            date,
+        },
+        remote_account_number: remote.map(|(_, number, _)| number.to_string()),
+        remote_account_name: remote_account_name.map(str::to_string),
+        kid_or_message: remote.and_then(|(_, _, kid)| kid.map(str::to_string)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(bank: &MockBank, name: &str) -> Account {
+        bank.accounts()
+            .unwrap()
+            .into_iter()
+            .find(|account| account.name == name)
+            .unwrap()
+    }
+
+    fn new_transactions(
+        bank: &MockBank,
+        account: &Account,
+        before: &[Transaction],
+    ) -> Vec<Transaction> {
+        bank.transactions(&account.key, &DateRange::default())
+            .unwrap()
+            .into_iter()
+            .filter(|tx| !before.iter().any(|old| old.id == tx.id))
+            .collect()
+    }
+
+    #[test]
+    fn transfer_moves_money_and_records_both_sides() {
+        let bank = MockBank::new(false);
+        let from = find(&bank, "Brukskonto");
+        let to = find(&bank, "Sparekonto");
+        let from_before = bank.transactions(&from.key, &DateRange::default()).unwrap();
+        let to_before = bank.transactions(&to.key, &DateRange::default()).unwrap();
+
+        let response = bank
+            .transfer(&from, &to, dec!(1000.50), Some("Sparing".to_string()))
+            .unwrap();
+        assert!(response.errors.is_empty());
+
+        let from_after = find(&bank, "Brukskonto");
+        let to_after = find(&bank, "Sparekonto");
+        assert_eq!(from_after.balance, from.balance - dec!(1000.50));
+        assert_eq!(
+            from_after.available_balance,
+            from.available_balance - dec!(1000.50)
+        );
+        assert_eq!(to_after.balance, to.balance + dec!(1000.50));
+        assert_eq!(
+            to_after.available_balance,
+            to.available_balance + dec!(1000.50)
+        );
+
+        let debit = new_transactions(&bank, &from, &from_before);
+        let credit = new_transactions(&bank, &to, &to_before);
+        assert_eq!(debit.len(), 1);
+        assert_eq!(credit.len(), 1);
+        let (debit, credit) = (&debit[0], &credit[0]);
+
+        assert_eq!(debit.amount, dec!(-1000.50));
+        assert_eq!(credit.amount, dec!(1000.50));
+        assert_eq!(debit.date, credit.date);
+        assert_ne!(debit.id, credit.id);
+        assert_eq!(debit.description.as_deref(), Some("Sparing"));
+        assert_eq!(
+            debit.remote_account_number.as_ref(),
+            Some(&to.account_number)
+        );
+        assert_eq!(
+            credit.remote_account_number.as_ref(),
+            Some(&from.account_number)
+        );
+    }
+
+    #[test]
+    fn fail_transfers_rejects_with_error_dto() {
+        let bank = MockBank::new(true);
+        let from = find(&bank, "Brukskonto");
+        let to = find(&bank, "Sparekonto");
+
+        let err = bank.transfer(&from, &to, dec!(100), None).unwrap_err();
+        let ApiError::Http { status, errors } = err else {
+            panic!("expected an HTTP error, got {:?}", err);
+        };
+        assert_eq!(status, 422);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "mock.simulated_failure");
+
+        assert_eq!(find(&bank, "Brukskonto").balance, from.balance);
+        assert_eq!(find(&bank, "Sparekonto").balance, to.balance);
+    }
+}
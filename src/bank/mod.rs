@@ -0,0 +1,60 @@
+use chrono::{DateTime, Local, NaiveDate};
+use clap::Args;
+use rust_decimal::Decimal;
+
+use crate::{
+    error::ApiError,
+    models::{Account, Transaction, TransferResponse},
+};
+
+mod mock;
+mod sparebank1;
+
+pub use mock::MockBank;
+pub use sparebank1::SpareBank1;
+
+// Booking dates to fetch transactions for, both ends inclusive. A missing end leaves it up to the
+// bank, which without any dates returns its default window of recent transactions.
+#[derive(Args, Default, Clone, Copy, Debug)]
+pub struct DateRange {
+    /// First booking date to include, e.g. 2025-01-01
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+    /// Last booking date to include
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, timestamp_ms: i64) -> bool {
+        let date = booking_date(timestamp_ms);
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+// The local calendar date of a transaction timestamp
+pub fn booking_date(timestamp_ms: i64) -> NaiveDate {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .date_naive()
+}
+
+// Everything the UI needs from a bank. Implementations decide how accounts are fetched and how a
+// transfer is routed (e.g. SpareBank 1 uses a separate endpoint for credit cards). Calls are made
+// from background threads, hence Send + Sync.
+pub trait BankProvider: Send + Sync {
+    fn accounts(&self) -> Result<Vec<Account>, ApiError>;
+    fn transactions(
+        &self,
+        account_key: &str,
+        range: &DateRange,
+    ) -> Result<Vec<Transaction>, ApiError>;
+    fn transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: Decimal,
+        message: Option<String>,
+    ) -> Result<TransferResponse, ApiError>;
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Local};
+use log::debug;
+use rust_decimal::Decimal;
+
+use crate::{
+    api,
+    auth::Credentials,
+    bank::{BankProvider, DateRange},
+    error::ApiError,
+    models::{Account, CreateTransferDTO, Transaction, TransferResponse, TransferToCreditCardDTO},
+};
+
+const PAGE_SIZE: usize = 1000;
+
+pub struct SpareBank1 {
+    base_url: String,
+    credentials: Credentials,
+}
+
+impl SpareBank1 {
+    pub fn new(base_url: &str, credentials: Credentials) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials,
+        }
+    }
+}
+
+impl BankProvider for SpareBank1 {
+    fn accounts(&self) -> Result<Vec<Account>, ApiError> {
+        Ok(api::get_accounts(&self.base_url, &self.credentials)?.accounts)
+    }
+
+    // The API caps each response at `rowLimit` rows, so a full page means there may be more. Paging
+    // continues from the oldest date in the page, and rows already seen on that day are skipped.
+    fn transactions(
+        &self,
+        account_key: &str,
+        range: &DateRange,
+    ) -> Result<Vec<Transaction>, ApiError> {
+        let mut transactions: Vec<Transaction> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut to = range.to;
+
+        loop {
+            let page = api::get_transactions(
+                &self.base_url,
+                &self.credentials,
+                account_key,
+                range.from,
+                to,
+                PAGE_SIZE,
+            )?
+            .transactions;
+            let full = page.len() >= PAGE_SIZE;
+            let oldest = page.iter().map(|tx| tx.date).min();
+
+            let before = transactions.len();
+            transactions.extend(page.into_iter().filter(|tx| seen.insert(tx.id.clone())));
+            debug!(
+                "Fetched {} transactions for {} up to {:?}",
+                transactions.len() - before,
+                account_key,
+                to
+            );
+
+            match oldest.and_then(DateTime::from_timestamp_millis) {
+                Some(oldest) if full && transactions.len() > before => {
+                    to = Some(oldest.with_timezone(&Local).date_naive());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(transactions)
+    }
+
+    fn transfer(
+        &self,
+        from: &Account,
+        to: &Account,
+        amount: Decimal,
+        message: Option<String>,
+    ) -> Result<TransferResponse, ApiError> {
+        if to.type_field == "CREDITCARD" {
+            // Credit card transfer - does not support message field
+            let credit_card_id = to.credit_card_account_id.as_ref().ok_or_else(|| {
+                ApiError::MissingCreditCardId {
+                    account: to.name.clone(),
+                }
+            })?;
+
+            let transfer = TransferToCreditCardDTO {
+                amount,
+                due_date: None,
+                from_account: from.account_number.clone(),
+                credit_card_account_id: credit_card_id.clone(),
+            };
+
+            debug!("Performing credit card transfer: {:?}", transfer);
+            api::create_credit_card_transfer(&self.base_url, &self.credentials, transfer)
+        } else {
+            let transfer = CreateTransferDTO {
+                amount,
+                due_date: None,
+                message,
+                to_account: to.account_number.clone(),
+                from_account: from.account_number.clone(),
+                currency_code: None,
+            };
+
+            debug!("Performing transfer: {:?}", transfer);
+            api::create_transfer(&self.base_url, &self.credentials, transfer)
+        }
+    }
+}
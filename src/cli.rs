@@ -0,0 +1,480 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use log::debug;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    amount,
+    bank::{BankProvider, DateRange},
+    export::{self, ExportFormat},
+    fileio::{self, ExportConfig},
+    models::{Account, Transaction, TransferReceipt},
+    ui,
+};
+
+#[derive(Parser)]
+#[command(version, about = "A terminal-based banking application")]
+pub struct Cli {
+    /// Use the built-in offline mock bank
+    #[arg(long, global = true)]
+    pub mock: bool,
+
+    /// Make every mock transfer fail
+    #[arg(long, global = true)]
+    pub mock_errors: bool,
+
+    /// Log in by pasting the redirect URL instead of opening a browser, e.g. over SSH
+    #[arg(long, global = true)]
+    pub headless: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// Without a subcommand the full-screen TUI is started
+#[derive(Subcommand)]
+pub enum Command {
+    /// List all accounts with balances
+    Accounts {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// List transactions for an account
+    Transactions {
+        /// Account name, account number or key
+        account: String,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        #[command(flatten)]
+        range: DateRange,
+    },
+    /// Print all accounts and their transactions, e.g. for piping into jq
+    Dump {
+        /// Only include transactions for this account (name, account number or key)
+        #[arg(long)]
+        account: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+        #[command(flatten)]
+        range: DateRange,
+    },
+    /// Export an account's transactions to a file, or stdout if no output is given
+    Export {
+        /// Account name, account number or key
+        account: String,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// CSV field delimiter, overrides the config file
+        #[arg(long)]
+        delimiter: Option<char>,
+        /// CSV decimal separator, overrides the config file
+        #[arg(long)]
+        decimal_separator: Option<char>,
+        #[command(flatten)]
+        range: DateRange,
+    },
+    /// Transfer money between two of your accounts
+    Transfer {
+        /// Account to transfer from (name, account number or key)
+        #[arg(long)]
+        from: String,
+        /// Account to transfer to (name, account number or key)
+        #[arg(long)]
+        to: String,
+        /// Amount, e.g. 1234,50
+        #[arg(long)]
+        amount: String,
+        /// Optional message, not supported for credit card transfers
+        #[arg(long)]
+        message: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+pub fn run(
+    command: Command,
+    bank: &dyn BankProvider,
+    export_config: &ExportConfig,
+    keep_receipts: bool,
+) -> Result<(), String> {
+    match command {
+        Command::Accounts { format } => print_accounts(bank, format),
+        Command::Transactions {
+            account,
+            format,
+            range,
+        } => print_transactions(bank, &account, format, &range),
+        Command::Dump {
+            account,
+            format,
+            range,
+        } => dump(bank, account.as_deref(), format, &range),
+        Command::Export {
+            account,
+            format,
+            output,
+            delimiter,
+            decimal_separator,
+            range,
+        } => {
+            let config = ExportConfig {
+                delimiter: delimiter.unwrap_or(export_config.delimiter),
+                decimal_separator: decimal_separator.unwrap_or(export_config.decimal_separator),
+                ..export_config.clone()
+            };
+            export(bank, &account, format, output, &range, &config)
+        }
+        Command::Transfer {
+            from,
+            to,
+            amount,
+            message,
+        } => transfer(bank, &from, &to, &amount, message, keep_receipts),
+    }
+}
+
+fn print_accounts(bank: &dyn BankProvider, format: OutputFormat) -> Result<(), String> {
+    let accounts = bank.accounts().map_err(|err| err.to_string())?;
+
+    match format {
+        OutputFormat::Table => {
+            print_accounts_table(&mut io::stdout(), &accounts).or_else(ignore_broken_pipe)?
+        }
+        OutputFormat::Json => print_json(&accounts)?,
+        OutputFormat::Csv => print_csv(accounts.iter().map(AccountRow::from))?,
+    }
+    Ok(())
+}
+
+fn print_transactions(
+    bank: &dyn BankProvider,
+    query: &str,
+    format: OutputFormat,
+    range: &DateRange,
+) -> Result<(), String> {
+    let accounts = bank.accounts().map_err(|err| err.to_string())?;
+    let account = find_account(&accounts, query)?;
+    let transactions = bank
+        .transactions(&account.key, range)
+        .map_err(|err| err.to_string())?;
+
+    match format {
+        OutputFormat::Table => print_transactions_table(&mut io::stdout(), &transactions)
+            .or_else(ignore_broken_pipe)?,
+        OutputFormat::Json => print_json(&transactions)?,
+        OutputFormat::Csv => print_csv(transactions.iter().map(TransactionRow::from))?,
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Dump<'a> {
+    accounts: &'a [Account],
+    transactions: Vec<Transaction>,
+}
+
+// Fetches accounts and the transactions of every account (or just one) in a single document
+fn dump(
+    bank: &dyn BankProvider,
+    query: Option<&str>,
+    format: OutputFormat,
+    range: &DateRange,
+) -> Result<(), String> {
+    let accounts = bank.accounts().map_err(|err| err.to_string())?;
+    let selected: Vec<&Account> = match query {
+        Some(query) => vec![find_account(&accounts, query)?],
+        None => accounts.iter().collect(),
+    };
+
+    let mut transactions = Vec::new();
+    for account in &selected {
+        debug!("Fetching transactions for {}", account.name);
+        transactions.extend(
+            bank.transactions(&account.key, range)
+                .map_err(|err| err.to_string())?,
+        );
+    }
+
+    match format {
+        OutputFormat::Json => print_json(&Dump {
+            accounts: &accounts,
+            transactions,
+        })?,
+        // Transactions carry their account name and number, so one flat table is enough
+        OutputFormat::Csv => print_csv(transactions.iter().map(TransactionRow::from))?,
+        OutputFormat::Table => {
+            print_dump_table(&accounts, &selected, &transactions).or_else(ignore_broken_pipe)?
+        }
+    }
+    Ok(())
+}
+
+fn print_dump_table(
+    accounts: &[Account],
+    selected: &[&Account],
+    transactions: &[Transaction],
+) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    print_accounts_table(&mut out, accounts)?;
+    for account in selected {
+        writeln!(out)?;
+        writeln!(out, "{} ({})", account.name, account.account_number)?;
+        let account_transactions: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| tx.account_key == account.key)
+            .cloned()
+            .collect();
+        print_transactions_table(&mut out, &account_transactions)?;
+    }
+    Ok(())
+}
+
+fn print_accounts_table(out: &mut impl Write, accounts: &[Account]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<30} {:<16} {:>18} {:>18}",
+        "Account Name", "Account Number", "Balance", "Available"
+    )?;
+    for account in accounts {
+        writeln!(
+            out,
+            "{:<30} {:<16} {:>18} {:>18}",
+            account.name,
+            account.account_number,
+            account.balance_money().to_string(),
+            account.available_money().to_string()
+        )?;
+    }
+    Ok(())
+}
+
+fn print_transactions_table(out: &mut impl Write, transactions: &[Transaction]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<10} {:<40} {:>18} Type",
+        "Date", "Description", "Amount"
+    )?;
+    for tx in transactions {
+        writeln!(
+            out,
+            "{:<10} {:<40} {:>18} {}",
+            ui::format_timestamp(tx.date),
+            tx.display_description().unwrap_or("N/A"),
+            tx.money().to_string(),
+            tx.type_text
+        )?;
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    writeln!(io::stdout(), "{}", json).or_else(ignore_broken_pipe)
+}
+
+fn print_csv<T: Serialize>(rows: impl Iterator<Item = T>) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    for row in rows {
+        if let Err(err) = writer.serialize(row) {
+            return match err.into_kind() {
+                csv::ErrorKind::Io(err) => ignore_broken_pipe(err),
+                kind => Err(format!("{:?}", kind)),
+            };
+        }
+    }
+    writer.flush().or_else(ignore_broken_pipe)
+}
+
+// Output piped into e.g. `head` may be closed early, which is not an error for us
+fn ignore_broken_pipe(err: io::Error) -> Result<(), String> {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        Ok(())
+    } else {
+        Err(err.to_string())
+    }
+}
+
+// The models nest structs, which CSV can't represent, so they are flattened into these rows
+#[derive(Serialize)]
+struct AccountRow<'a> {
+    key: &'a str,
+    account_number: &'a str,
+    name: &'a str,
+    #[serde(rename = "type")]
+    type_field: &'a str,
+    currency_code: &'a str,
+    balance: Decimal,
+    available_balance: Decimal,
+}
+
+impl<'a> From<&'a Account> for AccountRow<'a> {
+    fn from(account: &'a Account) -> Self {
+        Self {
+            key: &account.key,
+            account_number: &account.account_number,
+            name: &account.name,
+            type_field: &account.type_field,
+            currency_code: &account.currency_code,
+            balance: account.balance,
+            available_balance: account.available_balance,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TransactionRow<'a> {
+    id: &'a str,
+    account_number: &'a str,
+    account_name: &'a str,
+    date: String,
+    description: &'a str,
+    amount: Decimal,
+    currency_code: &'a str,
+    type_text: &'a str,
+    booking_status: &'a str,
+}
+
+impl<'a> From<&'a Transaction> for TransactionRow<'a> {
+    fn from(tx: &'a Transaction) -> Self {
+        Self {
+            id: &tx.id,
+            account_number: &tx.account_number.formatted,
+            account_name: &tx.account_name,
+            date: ui::format_timestamp(tx.date),
+            description: tx.display_description().unwrap_or("N/A"),
+            amount: tx.amount,
+            currency_code: &tx.currency_code,
+            type_text: &tx.type_text,
+            booking_status: &tx.booking_status,
+        }
+    }
+}
+
+fn export(
+    bank: &dyn BankProvider,
+    query: &str,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    range: &DateRange,
+    config: &ExportConfig,
+) -> Result<(), String> {
+    let accounts = bank.accounts().map_err(|err| err.to_string())?;
+    let account = find_account(&accounts, query)?;
+    let mut transactions = bank
+        .transactions(&account.key, range)
+        .map_err(|err| err.to_string())?;
+
+    // An existing journal is appended to, skipping transactions it already has
+    let (writer, append): (Box<dyn Write>, bool) = match &output {
+        Some(path) if format.is_journal() && path.exists() => {
+            let journal = fs::read_to_string(path).map_err(|err| err.to_string())?;
+            let known: HashSet<String> = export::ledger::existing_ids(&journal);
+            let before = transactions.len();
+            transactions.retain(|tx| !known.contains(&tx.id));
+            debug!(
+                "Skipping {} transactions already in {}",
+                before - transactions.len(),
+                path.display()
+            );
+            let file = OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map_err(|err| err.to_string())?;
+            (Box::new(file), true)
+        }
+        Some(path) => (
+            Box::new(File::create(path).map_err(|err| err.to_string())?),
+            false,
+        ),
+        None => (Box::new(io::stdout()), false),
+    };
+
+    // Stdout may be piped into e.g. `head`
+    match export::write(format, writer, account, &transactions, config, append) {
+        Ok(written) => {
+            if let Some(path) = output {
+                eprintln!("Exported {} transactions to {}", written, path.display());
+            }
+            Ok(())
+        }
+        Err(err) => ignore_broken_pipe(err),
+    }
+}
+
+fn transfer(
+    bank: &dyn BankProvider,
+    from: &str,
+    to: &str,
+    amount: &str,
+    message: Option<String>,
+    keep_receipts: bool,
+) -> Result<(), String> {
+    let accounts = bank.accounts().map_err(|err| err.to_string())?;
+    let from = find_account(&accounts, from)?;
+    let to = find_account(&accounts, to)?;
+
+    let amount = amount::validate_transfer(from, to, amount).map_err(|err| err.to_string())?;
+    let message = message.filter(|message| !message.trim().is_empty());
+
+    let response = bank
+        .transfer(from, to, amount.amount, message.clone())
+        .map_err(|err| err.to_string())?;
+
+    if !response.errors.is_empty() {
+        let messages: Vec<String> = response
+            .errors
+            .iter()
+            .map(|error| format!("{} (trace ID {})", error.message, error.trace_id))
+            .collect();
+        return Err(format!("The bank rejected the transfer: {}", messages.join("; ")));
+    }
+
+    let receipt = TransferReceipt::new(from, to, &amount, &response, message);
+    if keep_receipts && let Err(err) = fileio::append_receipt(&receipt) {
+        debug!("Failed to save transfer receipt: {}", err);
+        eprintln!("Warning: the transfer receipt could not be saved: {}", err);
+    }
+
+    println!("Transferred {} from {} to {}", amount, from.name, to.name);
+    println!(
+        "Payment ID: {}  Status: {}",
+        response.payment_id.as_deref().unwrap_or("N/A"),
+        response.status.as_deref().unwrap_or("N/A")
+    );
+    Ok(())
+}
+
+// Matches on key, account number (with or without dots) or a case-insensitive name
+pub fn find_account<'a>(accounts: &'a [Account], query: &str) -> Result<&'a Account, String> {
+    let digits = query.replace(['.', ' '], "");
+
+    accounts
+        .iter()
+        .find(|account| account.key == query)
+        .or_else(|| {
+            accounts
+                .iter()
+                .find(|account| account.account_number.replace('.', "") == digits)
+        })
+        .or_else(|| {
+            accounts
+                .iter()
+                .find(|account| account.name.eq_ignore_ascii_case(query))
+        })
+        .ok_or_else(|| format!("No account matches '{}'", query))
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use crate::models::ErrorDTO;
+
+#[derive(Debug)]
+pub enum ApiError {
+    Network(reqwest::Error),
+    Http { status: u16, errors: Vec<ErrorDTO> },
+    AuthExpired,
+    Decode(serde_json::Error),
+    // A credit card account listed without the id needed to pay into it
+    MissingCreditCardId { account: String },
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(err) => write!(f, "Network error: {}", err),
+            ApiError::Http { status, errors } if errors.is_empty() => {
+                write!(f, "The bank returned HTTP {}", status)
+            }
+            ApiError::Http { status, errors } => {
+                let messages: Vec<&str> = errors
+                    .iter()
+                    .map(|error| {
+                        error
+                            .localized_message
+                            .as_ref()
+                            .and_then(|localized| localized.message.as_deref())
+                            .unwrap_or(&error.message)
+                    })
+                    .collect();
+                write!(f, "The bank returned HTTP {}: {}", status, messages.join("; "))
+            }
+            ApiError::AuthExpired => {
+                write!(f, "Your session has expired. Restart auox to log in again.")
+            }
+            ApiError::Decode(err) => write!(f, "Could not read the response from the bank: {}", err),
+            ApiError::MissingCreditCardId { account } => write!(
+                f,
+                "The bank did not give a credit card id for {}, so it can't be paid into",
+                account
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Network(err) => Some(err),
+            ApiError::Decode(err) => Some(err),
+            ApiError::Http { .. }
+            | ApiError::AuthExpired
+            | ApiError::MissingCreditCardId { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        ApiError::Network(err)
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(err: serde_json::Error) -> Self {
+        ApiError::Decode(err)
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Sqlite(rusqlite::Error),
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Sqlite(err) => write!(f, "Local database error: {}", err),
+            StoreError::Decode(err) => write!(f, "Could not read saved data: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StoreError::Sqlite(err) => Some(err),
+            StoreError::Decode(err) => Some(err),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        StoreError::Sqlite(err)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(err: serde_json::Error) -> Self {
+        StoreError::Decode(err)
+    }
+}
+
+#[derive(Debug)]
+pub enum TokenStoreError {
+    Io(std::io::Error),
+    Keyring(keyring::Error),
+    Decode(serde_json::Error),
+    Decrypt,
+    Passphrase(String),
+}
+
+impl fmt::Display for TokenStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenStoreError::Io(err) => write!(f, "Could not access the token file: {}", err),
+            TokenStoreError::Keyring(err) => write!(
+                f,
+                "Could not access the keyring: {}. Set token_storage = \"encrypted\" in config.toml to use a passphrase instead.",
+                err
+            ),
+            TokenStoreError::Decode(err) => write!(f, "Saved tokens are not in proper format: {}", err),
+            TokenStoreError::Decrypt => {
+                write!(f, "Could not decrypt the saved tokens. Is the passphrase right?")
+            }
+            TokenStoreError::Passphrase(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TokenStoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TokenStoreError::Io(err) => Some(err),
+            TokenStoreError::Keyring(err) => Some(err),
+            TokenStoreError::Decode(err) => Some(err),
+            TokenStoreError::Decrypt | TokenStoreError::Passphrase(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TokenStoreError {
+    fn from(err: std::io::Error) -> Self {
+        TokenStoreError::Io(err)
+    }
+}
+
+impl From<keyring::Error> for TokenStoreError {
+    fn from(err: keyring::Error) -> Self {
+        TokenStoreError::Keyring(err)
+    }
+}
+
+impl From<serde_json::Error> for TokenStoreError {
+    fn from(err: serde_json::Error) -> Self {
+        TokenStoreError::Decode(err)
+    }
+}
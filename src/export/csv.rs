@@ -0,0 +1,50 @@
+use std::io::{self, Write};
+
+use crate::{fileio::ExportConfig, models::Transaction, ui};
+
+const HEADER: [&str; 7] = [
+    "Date",
+    "Description",
+    "Amount",
+    "Currency",
+    "Type",
+    "Remote account",
+    "KID/message",
+];
+
+// Spreadsheet friendly CSV with the delimiter and decimal separator from the export config,
+// so e.g. Norwegian Excel opens it without an import wizard.
+pub fn write_transactions<W: Write>(
+    writer: W,
+    transactions: &[Transaction],
+    config: &ExportConfig,
+) -> Result<(), ::csv::Error> {
+    if !config.delimiter.is_ascii() {
+        let err = io::Error::new(io::ErrorKind::InvalidInput, "The CSV delimiter must be ASCII");
+        return Err(err.into());
+    }
+
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(config.delimiter as u8)
+        .from_writer(writer);
+
+    writer.write_record(HEADER)?;
+
+    for tx in transactions {
+        let description = tx.display_description().unwrap_or("");
+        let amount = format!("{:.2}", tx.amount).replace('.', &config.decimal_separator.to_string());
+
+        writer.write_record([
+            ui::format_timestamp(tx.date).as_str(),
+            description,
+            amount.as_str(),
+            tx.currency_code.as_str(),
+            tx.type_text.as_str(),
+            tx.remote_account_number.as_deref().unwrap_or(""),
+            tx.kid_or_message.as_deref().unwrap_or(""),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::models::{Account, Transaction};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct LedgerConfig {
+    // Ledger account name per bank account, keyed by account number or account name
+    pub accounts: HashMap<String, String>,
+    pub expenses_account: String,
+    pub income_account: String,
+}
+
+impl Default for LedgerConfig {
+    fn default() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            expenses_account: "Expenses:Unknown".to_string(),
+            income_account: "Income:Unknown".to_string(),
+        }
+    }
+}
+
+impl LedgerConfig {
+    pub fn account_name(&self, account: &Account) -> String {
+        let digits: String = account
+            .account_number
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+
+        [&account.account_number, &digits, &account.name]
+            .iter()
+            .find_map(|key| self.accounts.get(key.as_str()))
+            .cloned()
+            .unwrap_or_else(|| format!("Assets:Bank:{}", default_component(&account.name)))
+    }
+}
+
+// Writes one journal entry per transaction. Each entry carries the transaction id as an `id` tag
+// (metadata in beancount), which `existing_ids` uses to skip entries already in a journal. Only
+// booked transactions are included. `open_accounts` adds the `open` directives beancount needs
+// at the top of a new file.
+pub fn write_journal<W: Write>(
+    mut writer: W,
+    account: &Account,
+    transactions: &[Transaction],
+    config: &LedgerConfig,
+    dialect: Dialect,
+    open_accounts: bool,
+) -> io::Result<()> {
+    let asset_account = config.account_name(account);
+
+    // Oldest first, the way journals are usually kept
+    let mut transactions = super::booked(transactions);
+    transactions.sort_by_key(|tx| tx.date);
+
+    if dialect == Dialect::Beancount
+        && open_accounts
+        && let Some(first) = transactions.first()
+    {
+        let date = format_date(first.date, "%Y-%m-%d");
+        let mut opened = HashSet::new();
+        for name in [
+            &asset_account,
+            &config.expenses_account,
+            &config.income_account,
+        ] {
+            if opened.insert(name) {
+                writeln!(writer, "{} open {}", date, name)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    for tx in transactions {
+        let counter_account = if tx.amount.is_sign_negative() {
+            &config.expenses_account
+        } else {
+            &config.income_account
+        };
+        let description = tx.display_description().unwrap_or(&tx.type_text);
+        let payee = tx.remote_account_name.as_deref();
+        let amount = format!("{:.2} {}", tx.amount, tx.currency_code);
+
+        match dialect {
+            Dialect::Ledger => {
+                writeln!(
+                    writer,
+                    "{} * {}",
+                    format_date(tx.date, "%Y/%m/%d"),
+                    payee.unwrap_or(description)
+                )?;
+                if payee.is_some() {
+                    writeln!(writer, "    ; {}", description)?;
+                }
+                writeln!(writer, "    ; id: {}", tx.id)?;
+                writeln!(writer, "    {}  {}", asset_account, amount)?;
+                writeln!(writer, "    {}", counter_account)?;
+            }
+            Dialect::Hledger => {
+                // hledger splits "payee | note" into its payee and note fields
+                let text = match payee {
+                    Some(payee) => format!("{} | {}", payee, description),
+                    None => description.to_string(),
+                };
+                writeln!(
+                    writer,
+                    "{} * {}  ; id:{}",
+                    format_date(tx.date, "%Y-%m-%d"),
+                    text,
+                    tx.id
+                )?;
+                writeln!(writer, "    {}  {}", asset_account, amount)?;
+                writeln!(writer, "    {}", counter_account)?;
+            }
+            Dialect::Beancount => {
+                let payee = payee
+                    .map(|payee| format!("\"{}\" ", quote(payee)))
+                    .unwrap_or_default();
+                writeln!(
+                    writer,
+                    "{} * {}\"{}\"",
+                    format_date(tx.date, "%Y-%m-%d"),
+                    payee,
+                    quote(description)
+                )?;
+                writeln!(writer, "  id: \"{}\"", quote(&tx.id))?;
+                writeln!(writer, "  {}  {}", asset_account, amount)?;
+                writeln!(writer, "  {}", counter_account)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+// Collects the transaction ids tagged in an existing journal of any of the three dialects. The
+// tag is only looked for in a beancount metadata line or in the comment after `;`, since a
+// description like "Vipps id: 1234" can contain the same text.
+pub fn existing_ids(journal: &str) -> HashSet<String> {
+    journal
+        .lines()
+        .filter_map(|line| {
+            let trimmed = line.trim_start();
+            let value = if line.starts_with(char::is_whitespace) && trimmed.starts_with("id:") {
+                trimmed.strip_prefix("id:")
+            } else {
+                // hledger separates tags in a comment with commas
+                let (_, comment) = line.split_once(';')?;
+                comment
+                    .split(',')
+                    .find_map(|tag| tag.trim_start().strip_prefix("id:"))
+            }?;
+            let id = value.split_whitespace().next()?;
+            Some(id.trim_matches('"').to_string())
+        })
+        .collect()
+}
+
+fn format_date(timestamp_ms: i64, format: &str) -> String {
+    match DateTime::from_timestamp_millis(timestamp_ms) {
+        Some(dt) => {
+            let local: DateTime<Local> = dt.into();
+            local.format(format).to_string()
+        }
+        None => "1970-01-01".to_string(),
+    }
+}
+
+// Account name components must start with a capital letter and can't contain spaces
+fn default_component(name: &str) -> String {
+    let mut component: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    if let Some(first) = component.get(..1) {
+        component.replace_range(..1, &first.to_uppercase());
+    }
+    component
+}
+
+fn quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn transactions() -> Vec<Transaction> {
+        vec![
+            Transaction {
+                id: "tx-1".to_string(),
+                description: Some("Vipps id: 1234".to_string()),
+                remote_account_name: Some("Ola Nordmann".to_string()),
+                amount: dec!(-250),
+                currency_code: "NOK".to_string(),
+                booking_status: "BOOKED".to_string(),
+                date: 1_700_000_000_000,
+                ..Default::default()
+            },
+            Transaction {
+                id: "tx-2".to_string(),
+                description: Some("Lønn".to_string()),
+                amount: dec!(38500),
+                currency_code: "NOK".to_string(),
+                booking_status: "BOOKED".to_string(),
+                date: 1_700_100_000_000,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn journal(dialect: Dialect) -> String {
+        let account = Account {
+            name: "Brukskonto".to_string(),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_journal(
+            &mut out,
+            &account,
+            &transactions(),
+            &LedgerConfig::default(),
+            dialect,
+            true,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn existing_ids_reads_tags_of_every_dialect() {
+        let expected: HashSet<String> = ["tx-1", "tx-2"].map(String::from).into();
+        for dialect in [Dialect::Ledger, Dialect::Hledger, Dialect::Beancount] {
+            assert_eq!(existing_ids(&journal(dialect)), expected);
+        }
+    }
+
+    #[test]
+    fn existing_ids_ignores_id_in_descriptions() {
+        let journal = "2024-01-05 * Vipps id: 1234\n    Assets:Bank  -250.00 NOK\n";
+        assert!(existing_ids(journal).is_empty());
+        let journal = "2024/01/05 * Ola\n    ; Vipps id: 1234\n    ; id: tx-1\n";
+        assert_eq!(existing_ids(journal), HashSet::from(["tx-1".to_string()]));
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    fileio::ExportConfig,
+    models::{Account, Transaction},
+};
+
+pub mod csv;
+pub mod ledger;
+pub mod ofx;
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Ofx,
+    Ledger,
+    Hledger,
+    Beancount,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ofx => "ofx",
+            ExportFormat::Ledger => "ledger",
+            ExportFormat::Hledger => "journal",
+            ExportFormat::Beancount => "beancount",
+        }
+    }
+
+    // Plain-text accounting journals can be appended to on re-export
+    pub fn is_journal(&self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Ledger | ExportFormat::Hledger | ExportFormat::Beancount
+        )
+    }
+}
+
+// `append` is set when adding to an existing journal, which then already has its header. Returns
+// how many transactions were written, which leaves out the pending ones for all but CSV.
+pub fn write<W: Write>(
+    format: ExportFormat,
+    writer: W,
+    account: &Account,
+    transactions: &[Transaction],
+    config: &ExportConfig,
+    append: bool,
+) -> io::Result<usize> {
+    let written = match format {
+        ExportFormat::Csv => transactions.len(),
+        _ => booked(transactions).len(),
+    };
+
+    match format {
+        ExportFormat::Csv => {
+            csv::write_transactions(writer, transactions, config).map_err(csv_error_to_io)
+        }
+        ExportFormat::Ofx => ofx::write_statement(writer, account, transactions),
+        ExportFormat::Ledger | ExportFormat::Hledger | ExportFormat::Beancount => {
+            let dialect = match format {
+                ExportFormat::Ledger => ledger::Dialect::Ledger,
+                ExportFormat::Hledger => ledger::Dialect::Hledger,
+                _ => ledger::Dialect::Beancount,
+            };
+            ledger::write_journal(
+                writer,
+                account,
+                transactions,
+                &config.ledger,
+                dialect,
+                !append,
+            )
+        }
+    }?;
+    Ok(written)
+}
+
+// Keeps the underlying io::Error, so callers can tell a closed pipe from other failures
+fn csv_error_to_io(err: ::csv::Error) -> io::Error {
+    if !err.is_io_error() {
+        return io::Error::other(err.to_string());
+    }
+    match err.into_kind() {
+        ::csv::ErrorKind::Io(err) => err,
+        kind => io::Error::other(format!("{:?}", kind)),
+    }
+}
+
+// Pending transactions are left out of statements and journals since the bank assigns them a new
+// id once they are booked, and the id is what keeps re-imports from duplicating entries
+fn booked(transactions: &[Transaction]) -> Vec<&Transaction> {
+    transactions
+        .iter()
+        .filter(|tx| !tx.booking_status.eq_ignore_ascii_case("PENDING"))
+        .collect()
+}
+
+// Where the TUI writes an export of `account`, e.g. `~/Downloads/brukskonto-2025-10-01.csv`. A
+// number is added to the name if that day's file already exists, so an earlier export is kept.
+pub fn export_path(config: &ExportConfig, account: &Account, format: ExportFormat) -> PathBuf {
+    let directory = config
+        .directory
+        .clone()
+        .or_else(dirs::download_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let name: String = account
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let date = chrono::Local::now().format("%Y-%m-%d");
+
+    let mut path = directory.join(format!("{}-{}.{}", name, date, format.extension()));
+    let mut number = 1;
+    while path.exists() {
+        number += 1;
+        path = directory.join(format!(
+            "{}-{}-{}.{}",
+            name,
+            date,
+            number,
+            format.extension()
+        ));
+    }
+    path
+}
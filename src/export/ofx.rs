@@ -0,0 +1,138 @@
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{Account, Transaction};
+
+// OFX only allows 32 characters in <NAME>
+const MAX_NAME_LENGTH: usize = 32;
+
+// Writes an OFX 2.1.1 bank (or credit card) statement for `account`. The transaction id is used as
+// FITID, so importing the same statement twice does not duplicate entries. Only booked
+// transactions are included.
+pub fn write_statement<W: Write>(
+    mut writer: W,
+    account: &Account,
+    transactions: &[Transaction],
+) -> io::Result<()> {
+    let transactions = super::booked(transactions);
+
+    let now = ofx_date(Utc::now().timestamp_millis());
+    let start = transactions.iter().map(|tx| tx.date).min();
+    let end = transactions.iter().map(|tx| tx.date).max();
+    let account_id: String = account
+        .account_number
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    let is_credit_card = account.type_field == "CREDITCARD";
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#)?;
+    writeln!(
+        writer,
+        r#"<?OFX OFXHEADER="200" VERSION="211" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>"#
+    )?;
+    writeln!(writer, "<OFX>")?;
+    writeln!(writer, "<SIGNONMSGSRSV1><SONRS>")?;
+    writeln!(writer, "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>")?;
+    writeln!(writer, "<DTSERVER>{}</DTSERVER><LANGUAGE>NOR</LANGUAGE>", now)?;
+    writeln!(writer, "</SONRS></SIGNONMSGSRSV1>")?;
+
+    if is_credit_card {
+        writeln!(writer, "<CREDITCARDMSGSRSV1><CCSTMTTRNRS><TRNUID>0</TRNUID>")?;
+        writeln!(writer, "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>")?;
+        writeln!(writer, "<CCSTMTRS><CURDEF>{}</CURDEF>", escape(&account.currency_code))?;
+        writeln!(writer, "<CCACCTFROM><ACCTID>{}</ACCTID></CCACCTFROM>", account_id)?;
+    } else {
+        let account_type = if account.account_properties.is_savings_account {
+            "SAVINGS"
+        } else {
+            "CHECKING"
+        };
+        writeln!(writer, "<BANKMSGSRSV1><STMTTRNRS><TRNUID>0</TRNUID>")?;
+        writeln!(writer, "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>")?;
+        writeln!(writer, "<STMTRS><CURDEF>{}</CURDEF>", escape(&account.currency_code))?;
+        // Norwegian account numbers start with the bank's four digit registration number
+        writeln!(
+            writer,
+            "<BANKACCTFROM><BANKID>{}</BANKID><ACCTID>{}</ACCTID><ACCTTYPE>{}</ACCTTYPE></BANKACCTFROM>",
+            account_id.get(..4).unwrap_or(&account_id),
+            account_id,
+            account_type
+        )?;
+    }
+
+    writeln!(writer, "<BANKTRANLIST>")?;
+    writeln!(
+        writer,
+        "<DTSTART>{}</DTSTART><DTEND>{}</DTEND>",
+        start.map(ofx_date).unwrap_or_else(|| now.clone()),
+        end.map(ofx_date).unwrap_or_else(|| now.clone())
+    )?;
+    for tx in transactions {
+        write_transaction(&mut writer, tx)?;
+    }
+    writeln!(writer, "</BANKTRANLIST>")?;
+
+    writeln!(
+        writer,
+        "<LEDGERBAL><BALAMT>{:.2}</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>",
+        account.balance, now
+    )?;
+    writeln!(
+        writer,
+        "<AVAILBAL><BALAMT>{:.2}</BALAMT><DTASOF>{}</DTASOF></AVAILBAL>",
+        account.available_balance, now
+    )?;
+
+    if is_credit_card {
+        writeln!(writer, "</CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>")?;
+    } else {
+        writeln!(writer, "</STMTRS></STMTTRNRS></BANKMSGSRSV1>")?;
+    }
+    writeln!(writer, "</OFX>")?;
+    writer.flush()
+}
+
+fn write_transaction<W: Write>(writer: &mut W, tx: &Transaction) -> io::Result<()> {
+    let transaction_type = if tx.amount.is_sign_negative() {
+        "DEBIT"
+    } else {
+        "CREDIT"
+    };
+    let full_name = tx
+        .display_description()
+        .or(tx.remote_account_name.as_deref())
+        .unwrap_or(&tx.type_text);
+    let name: String = full_name.chars().take(MAX_NAME_LENGTH).collect();
+
+    writeln!(writer, "<STMTTRN>")?;
+    writeln!(writer, "<TRNTYPE>{}</TRNTYPE>", transaction_type)?;
+    writeln!(writer, "<DTPOSTED>{}</DTPOSTED>", ofx_date(tx.date))?;
+    writeln!(writer, "<TRNAMT>{:.2}</TRNAMT>", tx.amount)?;
+    writeln!(writer, "<FITID>{}</FITID>", escape(&tx.id))?;
+    writeln!(writer, "<NAME>{}</NAME>", escape(&name))?;
+
+    let memo = tx
+        .kid_or_message
+        .as_ref()
+        .or(tx.description.as_ref())
+        .filter(|memo| memo.as_str() != full_name);
+    if let Some(memo) = memo {
+        writeln!(writer, "<MEMO>{}</MEMO>", escape(memo))?;
+    }
+    writeln!(writer, "</STMTTRN>")
+}
+
+fn ofx_date(timestamp_ms: i64) -> String {
+    match DateTime::from_timestamp_millis(timestamp_ms) {
+        Some(dt) => dt.format("%Y%m%d%H%M%S[0:GMT]").to_string(),
+        None => "19700101000000[0:GMT]".to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -1,7 +1,15 @@
-use crate::models::TokenData;
+use crate::{
+    export::ledger::LedgerConfig,
+    models::TransferReceipt,
+    sort::SortSettings,
+};
 use log::debug;
 use serde::Deserialize;
-use std::{fs, path::PathBuf};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
 
 fn app_config_dir() -> Option<PathBuf> {
     dirs::config_dir().map(|base| base.join("auox"))
@@ -34,6 +42,95 @@ pub struct AppConfig {
     pub client_id: String,
     pub client_secret: String,
     pub financial_institution: String,
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    pub oauth_authorize_url: Option<String>,
+    pub oauth_token_url: Option<String>,
+    #[serde(default)]
+    pub bank: BankKind,
+    #[serde(default)]
+    pub mock_errors: bool,
+    // Seconds between automatic balance refreshes in the TUI, off when unset
+    pub auto_refresh: Option<u64>,
+    #[serde(default)]
+    pub token_storage: TokenStorage,
+    #[serde(default)]
+    pub headless_login: bool,
+    #[serde(default = "default_callback_address")]
+    pub callback_address: String,
+    #[serde(default = "default_callback_port")]
+    pub callback_port: u16,
+    #[serde(default)]
+    pub export: ExportConfig,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub delimiter: char,
+    pub decimal_separator: char,
+    pub directory: Option<PathBuf>,
+    pub ledger: LedgerConfig,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: ';',
+            decimal_separator: ',',
+            directory: None,
+            ledger: LedgerConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn authorize_url(&self) -> String {
+        self.oauth_authorize_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/oauth/authorize", self.api_base_url.trim_end_matches('/')))
+    }
+
+    // Must match a redirect URI registered for the client at the bank
+    pub fn redirect_uri(&self) -> String {
+        format!("http://localhost:{}", self.callback_port)
+    }
+
+    pub fn token_url(&self) -> String {
+        self.oauth_token_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/oauth/token", self.api_base_url.trim_end_matches('/')))
+    }
+}
+
+fn default_api_base_url() -> String {
+    "https://api.sparebank1.no".to_string()
+}
+
+fn default_callback_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_callback_port() -> u16 {
+    8321
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BankKind {
+    #[default]
+    Sparebank1,
+    Mock,
+}
+
+// Where the OAuth tokens are kept between runs
+#[derive(Deserialize, Default, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStorage {
+    #[default]
+    File,
+    Keyring,
+    Encrypted,
 }
 
 pub fn get_config_file() -> AppConfig {
@@ -52,50 +149,89 @@ pub fn get_config_file() -> AppConfig {
     }
 }
 
-pub fn read_access_token_file() -> Option<TokenData> {
-    let dir = match app_data_dir() {
-        Some(path) => path,
-        None => {
-            panic!("Could not determine data directory")
+// Like `get_config_file`, but for modes that can run without credentials (e.g. --mock), so a
+// missing or incomplete config file is not an error.
+pub fn read_config_file() -> Option<AppConfig> {
+    let conf_path = config_file_path()?;
+    let file = fs::read_to_string(&conf_path).ok()?;
+
+    match toml::from_str(&file) {
+        Ok(config) => Some(config),
+        Err(err) => {
+            debug!("Ignoring config.toml: {}", err);
+            None
         }
-    };
+    }
+}
 
-    debug!("App data dir: {}", dir.display());
+// Files auox keeps in its data directory, e.g. `~/.local/share/auox/` on Linux. None if the
+// platform has no data directory.
+fn data_file(name: &str) -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join(name))
+}
 
-    std::fs::create_dir_all(&dir).expect("Failed to create data dir");
+pub fn token_file_path() -> Option<PathBuf> {
+    data_file("auth.json")
+}
 
-    let token_path = dir.join("auth.json");
+pub fn encrypted_token_file_path() -> Option<PathBuf> {
+    data_file("auth.enc")
+}
 
-    let file_content = fs::read_to_string(&token_path);
+pub fn receipts_file_path() -> Option<PathBuf> {
+    data_file("receipts.jsonl")
+}
 
-    match file_content {
-        Ok(token) => {
-            let token_data: TokenData =
-                serde_json::from_str(&token).expect("auth.json is not in proper format");
-            Some(token_data)
-        }
-        Err(_) => None,
-    }
+pub fn database_file_path() -> Option<PathBuf> {
+    data_file("auox.sqlite")
 }
 
-pub fn save_token_data_file(token_data: &TokenData) {
-    let dir = match app_data_dir() {
-        Some(path) => path,
-        None => {
-            panic!("Could not determine data directory")
-        }
-    };
+// One JSON object per line, so the log can be appended to without rewriting it
+pub fn append_receipt(receipt: &TransferReceipt) -> std::io::Result<()> {
+    let receipts_path = receipts_file_path().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Could not determine data directory",
+        )
+    })?;
+    if let Some(dir) = receipts_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
 
-    std::fs::create_dir_all(&dir).expect("Failed to create data dir");
+    let line = serde_json::to_string(receipt)?;
 
-    let token_path = dir.join("auth.json");
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&receipts_path)?;
+    writeln!(file, "{}", line)?;
 
-    let json_content =
-        serde_json::to_string_pretty(token_data).expect("Failed to serialize token data");
+    debug!("Transfer receipt saved to {}", receipts_path.display());
+    Ok(())
+}
 
-    fs::write(&token_path, json_content).expect("Failed to write token data to file");
+fn sort_settings_path() -> Option<PathBuf> {
+    data_file("sort.json")
+}
 
-    debug!("Token data saved to {}", token_path.display());
+// A missing or unreadable file just means the default order
+pub fn read_sort_settings() -> SortSettings {
+    sort_settings_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_sort_settings(settings: &SortSettings) -> std::io::Result<()> {
+    let Some(path) = sort_settings_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(settings)?)?;
+    debug!("Sort settings saved to {}", path.display());
+    Ok(())
 }
 
 fn create_config_template(conf_path: &PathBuf) {
@@ -108,6 +244,49 @@ client_secret = "your-client-secret-here"
 # Your financial institution ID
 # Examples: fid-smn (SpareBank 1 Midt-Norge), fid-snn (SpareBank 1 SR-Bank), etc.
 financial_institution = "fid-smn"
+
+# API host and OAuth endpoints. Override these to point Auox at a sandbox, a local stand-in
+# server or a recording proxy. The OAuth URLs default to <api_base_url>/oauth/authorize and /oauth/token.
+# api_base_url = "https://api.sparebank1.no"
+# oauth_authorize_url = "https://api.sparebank1.no/oauth/authorize"
+# oauth_token_url = "https://api.sparebank1.no/oauth/token"
+
+# Use the built-in offline mock bank instead of SpareBank 1 (same as running with --mock).
+# Set mock_errors = true to make every mock transfer fail.
+# bank = "mock"
+# mock_errors = false
+
+# Refresh balances automatically every N seconds while the account list is shown.
+# Press r to refresh at any time.
+# auto_refresh = 300
+
+# Where to keep the OAuth tokens: "file" (auth.json, readable only by you), "keyring" (the
+# system keyring, e.g. GNOME Keyring or KWallet) or "encrypted" (auth.enc, protected by a
+# passphrase asked for at startup or taken from AUOX_PASSPHRASE).
+# token_storage = "file"
+
+# Login. The browser is sent back to http://localhost:<callback_port>, which must be a redirect
+# URI registered for your client. Over SSH, forward the port (ssh -L 8321:localhost:8321) or set
+# headless_login = true (same as --headless) to paste the redirect URL into the terminal instead.
+# callback_address = "127.0.0.1"
+# callback_port = 8321
+# headless_login = false
+
+# CSV export of transactions. The defaults suit spreadsheets with a Norwegian locale.
+# The directory defaults to your Downloads folder.
+# [export]
+# delimiter = ";"
+# decimal_separator = ","
+# directory = "/home/me/Documents/bank"
+#
+# Ledger, hledger and beancount export. Accounts are keyed by account number or name and
+# default to Assets:Bank:<Name>.
+# [export.ledger]
+# expenses_account = "Expenses:Unknown"
+# income_account = "Income:Unknown"
+# [export.ledger.accounts]
+# "1234.56.78901" = "Assets:Bank:Checking"
+# "Sparekonto" = "Assets:Bank:Savings"
 "#;
     fs::write(conf_path, template).expect("Failed to create config.toml template");
 
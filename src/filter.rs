@@ -0,0 +1,164 @@
+use rust_decimal::Decimal;
+
+use crate::{amount, models::Transaction};
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum Direction {
+    #[default]
+    All,
+    Income,
+    Expense,
+}
+
+// What the Transactions view is narrowed down to. The search query matches the description,
+// remote account name and KID/message; amount terms in it (`>500`, `<100`, `100..500`) limit the
+// amount regardless of sign, since income vs expense is filtered separately.
+#[derive(Default)]
+pub struct TransactionFilter {
+    pub query: String,
+    pub direction: Direction,
+    pub type_code: Option<String>,
+    pub booking_status: Option<String>,
+}
+
+struct Query {
+    terms: Vec<String>,
+    min: Option<Decimal>,
+    max: Option<Decimal>,
+}
+
+impl TransactionFilter {
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty()
+            || self.direction != Direction::All
+            || self.type_code.is_some()
+            || self.booking_status.is_some()
+    }
+
+    pub fn apply<'a>(&self, transactions: &'a [Transaction]) -> Vec<&'a Transaction> {
+        let query = parse_query(&self.query);
+        transactions
+            .iter()
+            .filter(|tx| self.matches(tx, &query))
+            .collect()
+    }
+
+    fn matches(&self, tx: &Transaction, query: &Query) -> bool {
+        let direction = match self.direction {
+            Direction::All => true,
+            Direction::Income => !tx.amount.is_sign_negative(),
+            Direction::Expense => tx.amount.is_sign_negative(),
+        };
+        let amount = tx.amount.abs();
+
+        direction
+            && self.type_code.as_ref().is_none_or(|code| &tx.type_code == code)
+            && self
+                .booking_status
+                .as_ref()
+                .is_none_or(|status| &tx.booking_status == status)
+            && query.min.is_none_or(|min| amount >= min)
+            && query.max.is_none_or(|max| amount <= max)
+            && query.terms.iter().all(|term| {
+                [
+                    tx.description.as_deref(),
+                    tx.cleaned_description.as_deref(),
+                    tx.remote_account_name.as_deref(),
+                    tx.kid_or_message.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(term))
+            })
+    }
+
+    // Short summary of the active filters for the table title
+    pub fn describe(&self, transactions: &[Transaction]) -> String {
+        let mut parts = Vec::new();
+        if !self.query.trim().is_empty() {
+            parts.push(format!("\"{}\"", self.query.trim()));
+        }
+        match self.direction {
+            Direction::All => {}
+            Direction::Income => parts.push("income".to_string()),
+            Direction::Expense => parts.push("expenses".to_string()),
+        }
+        if let Some(code) = &self.type_code {
+            // Show the readable type text rather than the code
+            let text = transactions
+                .iter()
+                .find(|tx| &tx.type_code == code)
+                .map_or(code.as_str(), |tx| tx.type_text.as_str());
+            parts.push(text.to_string());
+        }
+        if let Some(status) = &self.booking_status {
+            parts.push(status.to_lowercase());
+        }
+        parts.join(", ")
+    }
+
+    pub fn cycle_direction(&mut self) {
+        self.direction = match self.direction {
+            Direction::All => Direction::Income,
+            Direction::Income => Direction::Expense,
+            Direction::Expense => Direction::All,
+        };
+    }
+
+    pub fn cycle_type_code(&mut self, transactions: &[Transaction]) {
+        let codes = distinct(transactions.iter().map(|tx| &tx.type_code));
+        self.type_code = next(self.type_code.take(), codes);
+    }
+
+    pub fn cycle_booking_status(&mut self, transactions: &[Transaction]) {
+        let statuses = distinct(transactions.iter().map(|tx| &tx.booking_status));
+        self.booking_status = next(self.booking_status.take(), statuses);
+    }
+}
+
+fn parse_query(query: &str) -> Query {
+    let mut parsed = Query {
+        terms: Vec::new(),
+        min: None,
+        max: None,
+    };
+
+    for word in query.split_whitespace() {
+        if let Some(min) = word.strip_prefix('>').and_then(parse_amount) {
+            parsed.min = Some(min);
+        } else if let Some(max) = word.strip_prefix('<').and_then(parse_amount) {
+            parsed.max = Some(max);
+        } else if let Some((min, max)) = word.split_once("..")
+            && let (Some(min), Some(max)) = (parse_amount(min), parse_amount(max))
+        {
+            parsed.min = Some(min);
+            parsed.max = Some(max);
+        } else {
+            parsed.terms.push(word.to_lowercase());
+        }
+    }
+
+    parsed
+}
+
+fn parse_amount(input: &str) -> Option<Decimal> {
+    amount::parse_amount(input).ok()
+}
+
+fn distinct<'a>(values: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut values: Vec<String> = values.cloned().collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+// Steps through `options` and back to no filter after the last one
+fn next(current: Option<String>, options: Vec<String>) -> Option<String> {
+    match current {
+        None => options.into_iter().next(),
+        Some(current) => options
+            .into_iter()
+            .skip_while(|option| option != &current)
+            .nth(1),
+    }
+}
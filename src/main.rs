@@ -1,4 +1,7 @@
+use chrono::{DateTime, Local, Months, NaiveDate};
+use clap::Parser;
 use crossterm::{
+    clipboard::CopyToClipboard,
     event::{self, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -10,7 +13,8 @@ use ratatui::{
     Terminal,
 };
 use std::{
-    io,
+    fs, io,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use tachyonfx::{
@@ -20,24 +24,53 @@ use tachyonfx::{
 use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
 
-use crate::models::{Account, Transaction};
+use crate::{
+    amount::TransferError,
+    bank::{BankProvider, DateRange, MockBank, SpareBank1},
+    cli::Cli,
+    error::{ApiError, StoreError},
+    export::ExportFormat,
+    fileio::{AppConfig, BankKind, ExportConfig},
+    filter::TransactionFilter,
+    models::{Account, ErrorDTO, Money, Transaction, TransferReceipt, TransferResponse},
+    sort::{AccountColumn, SortSettings, TransactionColumn},
+    store::Store,
+    worker::{Loaded, TransferRequest, Worker},
+};
 
+mod amount;
 mod api;
 mod auth;
+mod bank;
+mod cli;
+mod error;
+mod export;
 mod fileio;
+mod filter;
 mod models;
+mod sort;
+mod store;
+mod token_store;
 mod ui;
+mod worker;
 
 #[derive(Clone, Copy)]
 pub enum View {
     Accounts,
     Menu,
     Transactions,
+    TransactionDetail,
     TransferSelect,
     TransferModal,
+    TransferConfirm,
+    TransferResult,
 }
 
 pub struct AppState {
+    // Runs bank calls in the background; results are applied in `handle_loaded`
+    pub worker: Worker,
+    // Local copy of accounts and transactions, None if the database couldn't be opened
+    pub store: Option<Store>,
     pub account_index: TableState,
     pub menu_index: ListState,
     pub transaction_index: TableState,
@@ -46,11 +79,126 @@ pub struct AppState {
     pub accounts: Vec<Account>,
     pub view_stack: Vec<View>,
     pub transactions: Vec<Transaction>,
-    pub from_account: Option<usize>,
-    pub to_account: Option<usize>,
+    // Transfer endpoints by account key, since refreshing or sorting reorders `accounts`
+    pub from_account: Option<String>,
+    pub to_account: Option<String>,
     pub amount_input: Input,
     pub message_input: Input,
     pub active_input: TransferInput,
+    pub error: Option<ApiError>,
+    pub transfer_outcome: Option<TransferOutcome>,
+    pub export_config: ExportConfig,
+    // Off for the mock bank, whose transfers must not end up in the real receipt log
+    pub keep_receipts: bool,
+    // One-off message shown in the help bar until the next key press
+    pub status: Option<String>,
+    // Start of the oldest month loaded with "load earlier" in the Transactions view
+    pub history_from: Option<NaiveDate>,
+    // When the balances on screen were fetched from the bank
+    pub accounts_updated: Option<DateTime<Local>>,
+    pub auto_refresh: Option<Duration>,
+    pub last_refresh: Instant,
+    pub filter: TransactionFilter,
+    pub search_input: Input,
+    // Keys go to the search field while it's open
+    pub searching: bool,
+    pub sort: SortSettings,
+    // Account keys in the order the bank lists them, restored when no sort column is chosen
+    pub bank_order: Vec<String>,
+}
+
+impl AppState {
+    // The transactions shown in the Transactions view, after search and filters
+    pub fn visible_transactions(&self) -> Vec<&Transaction> {
+        self.filter.apply(&self.transactions)
+    }
+
+    pub fn selected_transaction(&self) -> Option<&Transaction> {
+        let idx = self.transaction_index.selected()?;
+        self.visible_transactions().get(idx).copied()
+    }
+
+    // The accounts shown in the account table. `account_index` is an index into this list, not
+    // into `accounts`.
+    pub fn visible_accounts(&self) -> Vec<&Account> {
+        visible_accounts(&self.accounts, self.show_credit_card)
+    }
+
+    pub fn selected_account(&self) -> Option<&Account> {
+        let idx = self.account_index.selected()?;
+        self.visible_accounts().get(idx).copied()
+    }
+
+    // Replaces the accounts, keeping the same account selected after sorting
+    fn set_accounts(&mut self, accounts: Vec<Account>) {
+        self.bank_order = accounts.iter().map(|account| account.key.clone()).collect();
+        self.accounts = accounts;
+        self.sort_accounts();
+    }
+
+    fn sort_accounts(&mut self) {
+        let selected = self.selected_account_key();
+        sort::sort_accounts(&mut self.accounts, &self.sort.accounts, &self.bank_order);
+        self.select_account(selected);
+    }
+
+    fn toggle_credit_cards(&mut self) {
+        let selected = self.selected_account_key();
+        self.show_credit_card = !self.show_credit_card;
+        self.select_account(selected);
+    }
+
+    // Selects the account's row, or the first row if it is no longer shown
+    fn select_account(&mut self, key: Option<String>) {
+        let idx = key
+            .and_then(|key| {
+                self.visible_accounts()
+                    .iter()
+                    .position(|account| account.key == key)
+            })
+            .unwrap_or(0);
+        self.account_index.select(Some(idx));
+    }
+
+    fn account_by_key(&self, key: Option<&String>) -> Option<&Account> {
+        let key = key?;
+        self.accounts.iter().find(|account| &account.key == key)
+    }
+
+    pub fn transfer_from(&self) -> Option<&Account> {
+        self.account_by_key(self.from_account.as_ref())
+    }
+
+    pub fn transfer_to(&self) -> Option<&Account> {
+        self.account_by_key(self.to_account.as_ref())
+    }
+
+    fn selected_account_key(&self) -> Option<String> {
+        self.selected_account().map(|account| account.key.clone())
+    }
+
+    fn sort_transactions(&mut self) {
+        sort::sort_transactions(&mut self.transactions, &self.sort.transactions);
+    }
+
+    fn reset_filter(&mut self) {
+        self.filter = TransactionFilter::default();
+        self.search_input = Input::default();
+        self.searching = false;
+    }
+}
+
+pub enum TransferOutcome {
+    Sending,
+    Completed {
+        receipt: TransferReceipt,
+        // None when receipts are not kept
+        receipt_saved: Option<bool>,
+    },
+    Failed {
+        summary: String,
+        errors: Vec<ErrorDTO>,
+    },
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -114,12 +262,39 @@ impl QuitHoldState {
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
+    let cli = Cli::parse();
+
+    // --mock works without credentials, so the config file is only required for a real bank
+    let config = if cli.mock {
+        fileio::read_config_file()
+    } else {
+        Some(fileio::get_config_file())
+    };
+    let bank = connect_bank(&cli, config.as_ref());
+    let store = open_store(&cli, config.as_ref());
+    let keep_receipts = !uses_mock_bank(&cli, config.as_ref());
+    let auto_refresh = config
+        .as_ref()
+        .and_then(|config| config.auto_refresh)
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+    let export_config = config
+        .as_ref()
+        .map(|config| config.export.clone())
+        .unwrap_or_default();
+
+    // Subcommands print to stdout and exit without starting the TUI
+    if let Some(command) = cli.command {
+        if let Err(err) = cli::run(command, bank.as_ref(), &export_config, keep_receipts) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Panic hook restores terminal to working state on panic before exiting.
     set_up_panic_hook();
 
-    let config = fileio::get_config_file();
-    auth::auth(config.client_id, config.client_secret, config.financial_institution);
-
     // Setup terminal
     enable_raw_mode()?;
     execute!(io::stdout(), EnterAlternateScreen)?;
@@ -145,7 +320,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         transaction_index: TableState::new().with_selected(0),
         show_balance: false,
         show_credit_card: false,
-        accounts: get_accounts(),
+        accounts: vec![],
         view_stack: vec![View::Accounts],
         transactions: vec![],
         from_account: None,
@@ -153,7 +328,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         amount_input: Input::default(),
         message_input: Input::default(),
         active_input: TransferInput::Amount,
+        error: None,
+        transfer_outcome: None,
+        export_config,
+        keep_receipts,
+        status: None,
+        history_from: None,
+        accounts_updated: None,
+        auto_refresh,
+        last_refresh: Instant::now(),
+        filter: TransactionFilter::default(),
+        search_input: Input::default(),
+        searching: false,
+        sort: fileio::read_sort_settings(),
+        bank_order: vec![],
+        worker: Worker::new(Arc::from(bank)),
+        store,
     };
+    // Saved balances are shown until the bank answers
+    if let Some(store) = &app.store {
+        let saved = store.accounts().unwrap_or_default();
+        app.accounts_updated = store
+            .accounts_updated_at()
+            .ok()
+            .flatten()
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|updated| updated.with_timezone(&Local));
+        app.set_accounts(saved);
+    }
+    refresh_accounts(&mut app);
 
     loop {
         let elapsed = last_frame.elapsed();
@@ -161,18 +364,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let q_progress = quit_hold.progress();
 
+        while let Some(loaded) = app.worker.try_recv() {
+            handle_loaded(&mut app, loaded);
+        }
+
+        // Only while the account list is shown, so a transfer in progress isn't disturbed
+        if let Some(interval) = app.auto_refresh
+            && app.last_refresh.elapsed() >= interval
+            && matches!(app.view_stack.last(), Some(View::Accounts))
+            && !app.worker.is_busy()
+        {
+            debug!("Auto-refreshing accounts");
+            refresh_accounts(&mut app);
+        }
+
         ui::draw(&mut app, &mut terminal, &mut effects, elapsed, q_progress);
 
         if event::poll(std::time::Duration::from_millis(100))?
             && let Event::Key(key) = event::read()? {
+                app.status = None;
                 match (key.code, app.view_stack.last()) {
+                    // An error popup swallows input until it is dismissed
+                    (code, _) if app.error.is_some() && !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if matches!(code, KeyCode::Esc | KeyCode::Enter) {
+                            app.error = None;
+                        }
+                    }
                     (KeyCode::Down, Some(view)) => match view {
                         View::Accounts | View::TransferSelect => {
-                            let i = app
-                                .account_index
-                                .selected()
-                                .map_or(0, |i| (i + 1) % app.accounts.len());
-                            app.account_index.select(Some(i));
+                            let count = app.visible_accounts().len();
+                            if count > 0 {
+                                let i = app
+                                    .account_index
+                                    .selected()
+                                    .map_or(0, |i| (i + 1) % count);
+                                app.account_index.select(Some(i));
+                            }
                         }
                         View::Menu => {
                             let i = app
@@ -181,22 +408,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 .map_or(0, |i| (i + 1) % ui::MENU_ITEMS.len());
                             app.menu_index.select(Some(i));
                         }
-                        View::Transactions if !app.transactions.is_empty() => {
-                            let i = app
-                                .transaction_index
-                                .selected()
-                                .map_or(0, |i| (i + 1) % app.transactions.len());
-                            app.transaction_index.select(Some(i));
+                        View::Transactions => {
+                            let count = app.visible_transactions().len();
+                            if count > 0 {
+                                let i = app
+                                    .transaction_index
+                                    .selected()
+                                    .map_or(0, |i| (i + 1) % count);
+                                app.transaction_index.select(Some(i));
+                            }
                         }
                         _ => {}
                     },
                     (KeyCode::Up, Some(view)) => match view {
                         View::Accounts | View::TransferSelect => {
-                            let i = app
-                                .account_index
-                                .selected()
-                                .map_or(0, |i| (i + app.accounts.len() - 1) % app.accounts.len());
-                            app.account_index.select(Some(i));
+                            let count = app.visible_accounts().len();
+                            if count > 0 {
+                                let i = app
+                                    .account_index
+                                    .selected()
+                                    .map_or(0, |i| (i + count - 1) % count);
+                                app.account_index.select(Some(i));
+                            }
                         }
                         View::Menu => {
                             let i = app.menu_index.selected().map_or(0, |i| {
@@ -204,29 +437,132 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             });
                             app.menu_index.select(Some(i));
                         }
-                        View::Transactions if !app.transactions.is_empty() => {
-                            let i = app.transaction_index.selected().map_or(0, |i| {
-                                (i + app.transactions.len() - 1) % app.transactions.len()
-                            });
-                            app.transaction_index.select(Some(i));
+                        View::Transactions => {
+                            let count = app.visible_transactions().len();
+                            if count > 0 {
+                                let i = app
+                                    .transaction_index
+                                    .selected()
+                                    .map_or(0, |i| (i + count - 1) % count);
+                                app.transaction_index.select(Some(i));
+                            }
                         }
                         _ => {}
                     },
-                    (KeyCode::Enter, Some(&View::Accounts)) => app.view_stack.push(View::Menu),
+                    // Incremental search, the list is filtered as you type
+                    (code, Some(&View::Transactions))
+                        if app.searching && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        match code {
+                            KeyCode::Enter => app.searching = false,
+                            KeyCode::Esc => {
+                                app.searching = false;
+                                app.search_input = Input::default();
+                            }
+                            _ => {
+                                app.search_input.handle_event(&Event::Key(key));
+                            }
+                        }
+                        app.filter.query = app.search_input.value().to_string();
+                        app.transaction_index.select(Some(0));
+                    }
+                    (KeyCode::Enter, Some(&View::Transactions)) if app.selected_transaction().is_some() => {
+                        app.view_stack.push(View::TransactionDetail)
+                    }
+                    (KeyCode::Enter, Some(&View::TransactionDetail)) => {
+                        app.view_stack.pop();
+                    }
+                    (KeyCode::Char('a'), Some(&View::TransactionDetail)) => {
+                        let number = app
+                            .selected_transaction()
+                            .and_then(|tx| tx.remote_account_number.clone());
+                        copy_to_clipboard(&mut app, "Account number", number);
+                    }
+                    (KeyCode::Char('k'), Some(&View::TransactionDetail)) => {
+                        let kid = app
+                            .selected_transaction()
+                            .and_then(|tx| tx.kid_or_message.clone());
+                        copy_to_clipboard(&mut app, "KID/message", kid);
+                    }
+                    (KeyCode::Enter, Some(&View::Accounts)) if app.selected_account().is_some() => {
+                        app.view_stack.push(View::Menu)
+                    }
                     (KeyCode::Enter, Some(&View::Menu)) => handle_menu_select(&mut app),
                     (KeyCode::Enter, Some(&View::TransferSelect)) => {
-                        app.to_account = app.account_index.selected();
+                        app.to_account = app.selected_account_key();
                         app.view_stack.push(View::TransferModal);
                     }
-                    (KeyCode::Esc, _) => {
-                        if app.view_stack.len() > 1 {
-                            app.view_stack.pop();
-                        }
+                    // Nothing to close until the bank has answered
+                    (_, Some(&View::TransferResult))
+                        if matches!(app.transfer_outcome, Some(TransferOutcome::Sending)) => {}
+                    (KeyCode::Enter | KeyCode::Esc, Some(&View::TransferResult)) => {
+                        close_transfer_result(&mut app)
+                    }
+                    (KeyCode::Esc, _) if app.view_stack.len() > 1 => {
+                        app.view_stack.pop();
                     }
                     (KeyCode::Char('b'), Some(&View::Accounts)) => {
                         app.show_balance = !app.show_balance
                     }
-                    (KeyCode::Char('m'), _) => app.show_credit_card = !app.show_credit_card,
+                    (KeyCode::Char('m'), _) => app.toggle_credit_cards(),
+                    (KeyCode::Char('r'), Some(&View::Accounts)) => refresh_accounts(&mut app),
+                    (KeyCode::Char(c @ '1'..='3'), Some(&View::Accounts)) => {
+                        let column = match c {
+                            '1' => AccountColumn::Name,
+                            '2' => AccountColumn::Balance,
+                            _ => AccountColumn::Type,
+                        };
+                        app.sort.accounts.toggle(column);
+                        app.sort_accounts();
+                        save_sort_settings(&app.sort);
+                    }
+                    (KeyCode::Char(c @ '1'..='3'), Some(&View::Transactions)) => {
+                        let column = match c {
+                            '1' => TransactionColumn::Date,
+                            '2' => TransactionColumn::Description,
+                            _ => TransactionColumn::Amount,
+                        };
+                        app.sort.transactions.toggle(column);
+                        app.sort_transactions();
+                        app.transaction_index.select(Some(0));
+                        save_sort_settings(&app.sort);
+                    }
+                    (KeyCode::Char('r'), Some(&View::Transactions)) => {
+                        if let Some(account_key) = app.selected_account_key() {
+                            load_transactions(&mut app, &account_key);
+                        }
+                    }
+                    (KeyCode::Char('/'), Some(&View::Transactions)) => app.searching = true,
+                    (KeyCode::Char('i'), Some(&View::Transactions)) => {
+                        app.filter.cycle_direction();
+                        app.transaction_index.select(Some(0));
+                    }
+                    (KeyCode::Char('t'), Some(&View::Transactions)) => {
+                        app.filter.cycle_type_code(&app.transactions);
+                        app.transaction_index.select(Some(0));
+                    }
+                    (KeyCode::Char('s'), Some(&View::Transactions)) => {
+                        app.filter.cycle_booking_status(&app.transactions);
+                        app.transaction_index.select(Some(0));
+                    }
+                    (KeyCode::Char('x'), Some(&View::Transactions)) => {
+                        app.reset_filter();
+                        app.transaction_index.select(Some(0));
+                    }
+                    (KeyCode::Char('l'), Some(&View::Transactions)) => {
+                        load_earlier_transactions(&mut app)
+                    }
+                    (KeyCode::Char('e'), Some(&View::Transactions)) => {
+                        export_transactions(&mut app, ExportFormat::Csv)
+                    }
+                    (KeyCode::Char('o'), Some(&View::Transactions)) => {
+                        export_transactions(&mut app, ExportFormat::Ofx)
+                    }
+                    // Only an explicit 'y' moves money, so a stray Enter can't confirm a transfer
+                    (KeyCode::Char('y'), Some(&View::TransferConfirm)) => perform_transfer(&mut app),
+                    (KeyCode::Char('n'), Some(&View::TransferConfirm)) => {
+                        app.view_stack.pop();
+                    }
                     // Handle input in TransferModal
                     (_, Some(&View::TransferModal)) => {
                         match key.code {
@@ -238,13 +574,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 };
                             }
                             KeyCode::Enter => {
-                                api::perform_transfer(&mut app);
+                                confirm_transfer(&mut app);
                             }
                             KeyCode::Char(c) => {
                                 match app.active_input {
                                     TransferInput::Amount => {
-                                        // Only allow digits and decimal point for amount
-                                        if c.is_numeric() || c == '.' || c == ',' {
+                                        // Only allow digits, decimal separators and thousands spacing for amount
+                                        if c.is_ascii_digit() || c == '.' || c == ',' || c == ' ' {
                                             app.amount_input.handle_event(&Event::Key(key));
                                         }
                                     }
@@ -269,12 +605,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
 
                     //exit the application
-                    (KeyCode::Char('c'), _) if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if !exiting {
-                            effects.add_effect(fx::dissolve((500, Interpolation::QuintIn)));
-                            exiting = true;
-                            exit_start_time = Some(Instant::now());
-                        }
+                    (KeyCode::Char('c'), _)
+                        if key.modifiers.contains(KeyModifiers::CONTROL) && !exiting =>
+                    {
+                        effects.add_effect(fx::dissolve((500, Interpolation::QuintIn)));
+                        exiting = true;
+                        exit_start_time = Some(Instant::now());
                     }
                     (KeyCode::Char('q'), Some(view)) if !matches!(view, View::TransferModal) && !exiting => {
                         quit_hold.on_q_pressed();
@@ -302,16 +638,422 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-pub fn get_accounts() -> Vec<Account> {
-    debug!("Fetching accounts");
-    let data = api::get_accounts();
-    data.accounts
+// Credit cards are hidden unless toggled on with 'm'
+pub fn visible_accounts(accounts: &[Account], show_credit_card: bool) -> Vec<&Account> {
+    accounts
+        .iter()
+        .filter(|account| show_credit_card || account.type_field != "CREDITCARD")
+        .collect()
+}
+
+fn connect_bank(cli: &Cli, config: Option<&AppConfig>) -> Box<dyn BankProvider> {
+    match config {
+        None => {
+            debug!("Using mock bank");
+            Box::new(MockBank::new(cli.mock_errors))
+        }
+        Some(config) if cli.mock || config.bank == BankKind::Mock => {
+            debug!("Using mock bank");
+            Box::new(MockBank::new(config.mock_errors || cli.mock_errors))
+        }
+        Some(config) => {
+            if let Err(err) = token_store::init(config.token_storage) {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+            if let Err(err) = auth::auth(config, cli.headless || config.headless_login) {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+            Box::new(SpareBank1::new(
+                &config.api_base_url,
+                auth::Credentials::new(config),
+            ))
+        }
+    }
+}
+
+fn uses_mock_bank(cli: &Cli, config: Option<&AppConfig>) -> bool {
+    config.is_none_or(|config| cli.mock || config.bank == BankKind::Mock)
+}
+
+// The mock bank gets a throwaway database so its data never mixes with real history
+fn open_store(cli: &Cli, config: Option<&AppConfig>) -> Option<Store> {
+    let store = if uses_mock_bank(cli, config) {
+        Store::open_in_memory()
+    } else if let Some(path) = fileio::database_file_path() {
+        Store::open(&path)
+    } else {
+        debug!("Continuing without local database: no data directory");
+        return None;
+    };
+
+    match store {
+        Ok(store) => Some(store),
+        Err(err) => {
+            debug!("Continuing without local database: {}", err);
+            None
+        }
+    }
+}
+
+// OSC 52 asks the terminal to set the clipboard, which also works over SSH
+fn copy_to_clipboard(app: &mut AppState, label: &str, value: Option<String>) {
+    let Some(value) = value.filter(|value| !value.is_empty()) else {
+        app.status = Some(format!("{} not available for this transaction", label));
+        return;
+    };
+
+    app.status = Some(
+        match execute!(io::stdout(), CopyToClipboard::to_clipboard_from(&value)) {
+            Ok(()) => format!("{} {} copied to the clipboard", label, value),
+            Err(err) => {
+                debug!("Copy to clipboard failed: {}", err);
+                format!("Could not copy to the clipboard: {}", err)
+            }
+        },
+    );
+}
+
+fn save_sort_settings(settings: &SortSettings) {
+    if let Err(err) = fileio::save_sort_settings(settings) {
+        debug!("Could not save sort settings: {}", err);
+    }
+}
+
+fn refresh_accounts(app: &mut AppState) {
+    app.last_refresh = Instant::now();
+    app.worker.accounts();
+}
+
+// Shows the saved history for the account right away, then merges in what the bank returns
+fn load_transactions(app: &mut AppState, account_key: &str) {
+    app.transactions = app
+        .store
+        .as_ref()
+        .and_then(|store| store.transactions(account_key).ok())
+        .unwrap_or_default();
+    app.sort_transactions();
+    app.worker
+        .transactions(account_key.to_string(), DateRange::default());
+}
+
+fn handle_loaded(app: &mut AppState, loaded: Loaded) {
+    match loaded {
+        Loaded::Accounts(Ok(accounts)) => {
+            if let Some(store) = app.store.as_mut()
+                && let Err(err) = store.save_accounts(&accounts)
+            {
+                debug!("Could not save accounts: {}", err);
+            }
+            app.set_accounts(accounts);
+            app.accounts_updated = Some(Local::now());
+        }
+        // Whatever is on screen was loaded from the local database, so keep showing it
+        Loaded::Accounts(Err(err)) if !app.accounts.is_empty() => {
+            debug!("Showing saved accounts: {}", err);
+            app.status = Some(format!("Offline, showing saved balances: {}", err));
+        }
+        Loaded::Accounts(Err(err)) => app.error = Some(err),
+        Loaded::Transactions {
+            account_key,
+            range,
+            result,
+        } => handle_loaded_transactions(app, &account_key, range, result),
+        Loaded::Transfer { request, result } => {
+            app.transfer_outcome = Some(transfer_outcome(*request, result, app.keep_receipts));
+        }
+    }
+}
+
+fn handle_loaded_transactions(
+    app: &mut AppState,
+    account_key: &str,
+    range: DateRange,
+    result: Result<Vec<Transaction>, ApiError>,
+) {
+    // The user may have moved on to another account while this was loading. The detail popup
+    // is drawn over the list, so the list is still on screen.
+    let showing = matches!(
+        app.view_stack.last(),
+        Some(View::Transactions | View::TransactionDetail)
+    )
+        && app
+            .selected_account()
+            .is_some_and(|account| account.key == account_key);
+    let earlier = range.from.is_some();
+
+    let fetched = match result {
+        Ok(fetched) => fetched,
+        Err(err) if !showing => {
+            debug!("Fetching transactions for {} failed: {}", account_key, err);
+            return;
+        }
+        Err(err) if !earlier && !app.transactions.is_empty() => {
+            debug!("Showing saved transactions: {}", err);
+            app.status = Some(format!("Offline, showing saved transactions: {}", err));
+            return;
+        }
+        Err(err) => {
+            app.error = Some(err);
+            return;
+        }
+    };
+
+    let synced = app.store.as_mut().map(|store| {
+        let summary = store.sync_transactions(account_key, &range, &fetched)?;
+        Ok::<_, StoreError>((summary, store.transactions(account_key)?))
+    });
+    if !showing {
+        return;
+    }
+
+    let added = fetched
+        .iter()
+        .filter(|tx| !app.transactions.iter().any(|shown| shown.id == tx.id))
+        .count();
+    let selected_id = app.selected_transaction().map(|tx| tx.id.clone());
+
+    let summary = match synced {
+        Some(Ok((summary, transactions))) => {
+            app.transactions = transactions;
+            Some(summary)
+        }
+        Some(Err(err)) => {
+            debug!("Could not sync transactions: {}", err);
+            merge_transactions(app, fetched, earlier);
+            None
+        }
+        None => {
+            merge_transactions(app, fetched, earlier);
+            None
+        }
+    };
+    app.sort_transactions();
+
+    // Keep the same transaction selected, so an open detail popup doesn't switch to another one
+    if let Some(idx) = selected_id.and_then(|id| {
+        app.visible_transactions()
+            .iter()
+            .position(|tx| tx.id == id)
+    }) {
+        app.transaction_index.select(Some(idx));
+    }
+
+    if let DateRange {
+        from: Some(from),
+        to: Some(to),
+    } = range
+    {
+        app.history_from = Some(from);
+        app.status = Some(if added == 0 {
+            format!("No more transactions between {} and {}", from, to)
+        } else {
+            format!("Loaded {} transactions back to {}", added, from)
+        });
+    } else if let Some(summary) = summary.filter(|s| s.added > 0 || s.booked > 0) {
+        app.status = Some(format!(
+            "Synced {} new and {} newly booked transactions",
+            summary.added, summary.booked
+        ));
+    }
+}
+
+// Without a local database, the default window replaces the list and earlier months are added to it
+fn merge_transactions(app: &mut AppState, fetched: Vec<Transaction>, earlier: bool) {
+    if !earlier {
+        app.transactions = fetched;
+        return;
+    }
+    let new: Vec<Transaction> = fetched
+        .into_iter()
+        .filter(|tx| !app.transactions.iter().any(|shown| shown.id == tx.id))
+        .collect();
+    app.transactions.extend(new);
+}
+
+// Validates the transfer form, returning the amount in the source account's currency
+pub fn validate_transfer_input(app: &AppState) -> Option<Result<Money, TransferError>> {
+    let from = app.transfer_from()?;
+    let to = app.transfer_to()?;
+    Some(amount::validate_transfer(from, to, app.amount_input.value()))
+}
+
+fn confirm_transfer(app: &mut AppState) {
+    match validate_transfer_input(app) {
+        Some(Ok(_)) => app.view_stack.push(View::TransferConfirm),
+        Some(Err(err)) => debug!("Not confirming transfer: {}", err),
+        None => debug!("Transfer accounts not selected"),
+    }
+}
+
+fn perform_transfer(app: &mut AppState) {
+    let Some(Ok(amount)) = validate_transfer_input(app) else {
+        debug!("Transfer is not valid, not performing it");
+        return;
+    };
+
+    let Some(from_account) = app.transfer_from() else {
+        debug!("No from_account selected");
+        return;
+    };
+
+    let Some(to_account) = app.transfer_to() else {
+        debug!("No to_account selected");
+        return;
+    };
+
+    let message = app.message_input.value().trim();
+    let message = if message.is_empty() {
+        None
+    } else {
+        Some(message.to_string())
+    };
+
+    // The result screen opens right away and waits for the bank, so the confirm key can't be
+    // pressed twice
+    app.worker.transfer(TransferRequest {
+        from: from_account.clone(),
+        to: to_account.clone(),
+        amount,
+        message,
+    });
+    app.transfer_outcome = Some(TransferOutcome::Sending);
+    app.view_stack.push(View::TransferResult);
+}
+
+fn transfer_outcome(
+    request: TransferRequest,
+    result: Result<TransferResponse, ApiError>,
+    keep_receipts: bool,
+) -> TransferOutcome {
+    let TransferRequest {
+        from,
+        to,
+        amount,
+        message,
+    } = request;
+
+    let outcome = match result {
+        Ok(response) if response.errors.is_empty() => {
+            debug!("Transfer successful! Payment ID: {:?}", response.payment_id);
+
+            let receipt =
+                TransferReceipt::new(&from, &to, &amount, &response, message);
+            let receipt_saved = keep_receipts.then(|| match fileio::append_receipt(&receipt) {
+                Ok(()) => true,
+                Err(err) => {
+                    debug!("Failed to save transfer receipt: {}", err);
+                    false
+                }
+            });
+
+            TransferOutcome::Completed {
+                receipt,
+                receipt_saved,
+            }
+        }
+        Ok(response) => {
+            debug!("Transfer failed with {} error(s)", response.errors.len());
+            TransferOutcome::Failed {
+                summary: "The bank rejected the transfer".to_string(),
+                errors: response.errors,
+            }
+        }
+        Err(err) => {
+            debug!("Transfer failed: {}", err);
+            match err {
+                ApiError::Http { status, errors } if !errors.is_empty() => TransferOutcome::Failed {
+                    summary: format!("The bank rejected the transfer (HTTP {})", status),
+                    errors,
+                },
+                err => TransferOutcome::Failed {
+                    summary: err.to_string(),
+                    errors: vec![],
+                },
+            }
+        }
+    };
+
+    if let TransferOutcome::Failed { errors, .. } = &outcome {
+        for error in errors {
+            debug!(
+                "  - [{}] {} (HTTP {}): {}",
+                error.code, error.trace_id, error.http_code, error.message
+            );
+        }
+    }
+
+    outcome
+}
+
+fn close_transfer_result(app: &mut AppState) {
+    match app.transfer_outcome.take() {
+        Some(TransferOutcome::Completed { .. }) => {
+            // Reset state
+            app.amount_input = Input::default();
+            app.message_input = Input::default();
+            app.from_account = None;
+            app.to_account = None;
+
+            app.view_stack.clear();
+            app.view_stack.push(View::Accounts);
+
+            refresh_accounts(app);
+        }
+        _ => {
+            // Back to the transfer form so the details can be corrected
+            app.view_stack.pop();
+            if let Some(View::TransferConfirm) = app.view_stack.last() {
+                app.view_stack.pop();
+            }
+        }
+    }
 }
 
-fn get_transactions(account_key: &String) -> Vec<Transaction> {
-    debug!("Fetching transactions");
-    let data = api::get_transactions(account_key);
-    data.transactions
+// Requests the month before the oldest transaction shown, which is added to the list once loaded
+fn load_earlier_transactions(app: &mut AppState) {
+    let Some(account_key) = app.selected_account_key() else {
+        return;
+    };
+
+    let oldest = app.transactions.iter().map(|tx| bank::booking_date(tx.date)).min();
+    let to = [oldest, app.history_from]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or_else(|| Local::now().date_naive());
+    let Some(from) = to.checked_sub_months(Months::new(1)) else {
+        return;
+    };
+    let range = DateRange {
+        from: Some(from),
+        to: Some(to),
+    };
+
+    app.worker.transactions(account_key, range);
+}
+
+fn export_transactions(app: &mut AppState, format: ExportFormat) {
+    let Some(account) = app.selected_account() else {
+        return;
+    };
+
+    let path = export::export_path(&app.export_config, account, format);
+    let result = fs::File::create_new(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| {
+            export::write(format, file, account, &app.transactions, &app.export_config, false)
+                .map_err(|err| err.to_string())
+        });
+
+    app.status = Some(match result {
+        Ok(written) => format!("Exported {} transactions to {}", written, path.display()),
+        Err(err) => {
+            debug!("Export to {} failed: {}", path.display(), err);
+            format!("Export failed: {}", err)
+        }
+    });
 }
 
 fn handle_menu_select(app: &mut AppState) {
@@ -324,19 +1066,19 @@ fn handle_menu_select(app: &mut AppState) {
     match new_view {
         View::Accounts => {}
         View::Transactions => {
-            let account_key = &app
-                .accounts
-                .get(app.account_index.selected().unwrap())
-                .unwrap()
-                .key;
-            let transactions = get_transactions(account_key);
-            app.transactions = transactions;
+            let account_key = app.selected_account_key().unwrap();
+            app.history_from = None;
+            app.reset_filter();
+            load_transactions(app, &account_key);
         }
         View::TransferSelect => {
             // Save the currently selected account as the from_account
-            app.from_account = app.account_index.selected();
+            app.from_account = app.selected_account_key();
         }
         View::TransferModal => {}
+        View::TransferConfirm => {}
+        View::TransferResult => {}
+        View::TransactionDetail => {}
         View::Menu => {}
     }
     app.view_stack.push(new_view);
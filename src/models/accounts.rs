@@ -1,6 +1,9 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::Money;
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountData {
@@ -16,8 +19,10 @@ pub struct Account {
     pub iban: String,
     pub name: String,
     pub description: String,
-    pub balance: f64,
-    pub available_balance: f64,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub balance: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub available_balance: Decimal,
     pub currency_code: String,
     pub owner: Option<Owner>,
     pub product_type: String,
@@ -26,11 +31,27 @@ pub struct Account {
     pub product_id: Option<String>,
     pub description_code: Option<String>,
     pub account_properties: AccountProperties,
-    pub credit_card_credit_limit: Option<f64>,
+    #[serde(with = "rust_decimal::serde::float_option", default)]
+    pub credit_card_credit_limit: Option<Decimal>,
     #[serde(rename = "creditCardAccountID")]
     pub credit_card_account_id: Option<String>,
 }
 
+impl Account {
+    pub fn balance_money(&self) -> Money {
+        Money::new(self.balance, &self.currency_code)
+    }
+
+    pub fn available_money(&self) -> Money {
+        Money::new(self.available_balance, &self.currency_code)
+    }
+
+    pub fn credit_limit_money(&self) -> Option<Money> {
+        self.credit_card_credit_limit
+            .map(|limit| Money::new(limit, &self.currency_code))
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Owner {
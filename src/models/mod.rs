@@ -1,9 +1,11 @@
 pub mod accounts;
+pub mod money;
 pub mod token;
 pub mod transactions;
 pub mod transfers;
 
 pub use accounts::*;
+pub use money::*;
 pub use token::*;
 pub use transactions::*;
 pub use transfers::*;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+// An exact amount in a given currency. Amounts from the API are deserialized straight into
+// `Decimal`, so sums and comparisons never pick up floating point rounding errors. They are
+// serialized back as JSON numbers, the same way the API sends them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Money {
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
+    }
+
+    // Sums amounts per currency, keeping the order in which currencies first appear
+    pub fn totals(items: impl IntoIterator<Item = Money>) -> Vec<Money> {
+        let mut totals: Vec<Money> = Vec::new();
+        for item in items {
+            match totals.iter_mut().find(|total| total.currency == item.currency) {
+                Some(total) => total.amount += item.amount,
+                None => totals.push(item),
+            }
+        }
+        totals
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} {}", self.amount, self.currency)
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+// Refresh this long before the access token runs out, so a request never races the expiry
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct TokenData {
     pub access_token: String,
     pub expires_in: u64,
@@ -8,4 +11,36 @@ pub struct TokenData {
     pub refresh_token_absolute_expires_in: u64,
     pub token_type: String,
     pub refresh_token: String,
-}
\ No newline at end of file
+    // Unix time the token was received. Not sent by the bank, and missing in files written by
+    // older versions.
+    #[serde(default)]
+    pub issued_at: Option<i64>,
+}
+
+impl TokenData {
+    pub fn issued_now(mut self) -> Self {
+        self.issued_at = Some(chrono::Utc::now().timestamp());
+        self
+    }
+
+    pub fn access_expires_at(&self) -> Option<i64> {
+        Some(self.issued_at? + self.expires_in as i64)
+    }
+
+    // The idle lifetime (`refresh_token_expires_in`) is left for the bank to judge, since using the
+    // token may extend it. Past the absolute lifetime no refresh can succeed.
+    pub fn refresh_expires_at(&self) -> Option<i64> {
+        Some(self.issued_at? + self.refresh_token_absolute_expires_in as i64)
+    }
+
+    pub fn is_access_valid(&self, now: i64) -> bool {
+        self.access_expires_at()
+            .is_some_and(|expires_at| now < expires_at - EXPIRY_MARGIN_SECS)
+    }
+
+    // Unknown for tokens without `issued_at`, in which case a refresh is worth trying
+    pub fn is_refresh_valid(&self, now: i64) -> bool {
+        self.refresh_expires_at()
+            .is_none_or(|expires_at| now < expires_at)
+    }
+}
@@ -1,6 +1,8 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::Money;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,7 +19,8 @@ pub struct Transaction {
     pub description: Option<String>,
     pub cleaned_description: Option<String>,
     pub account_number: AccountNumber,
-    pub amount: f64,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Decimal,
     pub date: i64,
     pub interest_date: Option<i64>,
     pub type_code: String,
@@ -37,6 +40,19 @@ pub struct Transaction {
     pub kid_or_message: Option<String>,
 }
 
+impl Transaction {
+    pub fn money(&self) -> Money {
+        Money::new(self.amount, &self.currency_code)
+    }
+
+    // The bank's cleaned-up description when it has one, e.g. without card numbers and dates
+    pub fn display_description(&self) -> Option<&str> {
+        self.cleaned_description
+            .as_deref()
+            .or(self.description.as_deref())
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccountNumber {
@@ -49,7 +65,8 @@ pub struct AccountNumber {
 #[serde(rename_all = "camelCase")]
 pub struct ClassificationInput {
     pub id: String,
-    pub amount: f64,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub amount: Decimal,
     #[serde(rename = "type")]
     pub type_field: String,
     pub text: Option<String>,
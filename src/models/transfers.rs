@@ -1,9 +1,12 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use super::{Account, Money};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTransferDTO {
-    pub amount: String,
+    pub amount: Decimal,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub due_date: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -17,7 +20,7 @@ pub struct CreateTransferDTO {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferToCreditCardDTO {
-    pub amount: String,
+    pub amount: Decimal,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub due_date: Option<String>,
     pub from_account: String,
@@ -50,3 +53,49 @@ pub struct LocalizedMessage {
     pub locale: Option<String>,
     pub message: Option<String>,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    #[serde(default)]
+    pub errors: Vec<ErrorDTO>,
+}
+
+// Written to the local receipt log after a transfer completes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferReceipt {
+    pub timestamp: String,
+    pub payment_id: Option<String>,
+    pub status: Option<String>,
+    pub from_account: String,
+    pub from_account_name: String,
+    pub to_account: String,
+    pub to_account_name: String,
+    pub amount: Decimal,
+    pub currency_code: String,
+    pub message: Option<String>,
+}
+
+impl TransferReceipt {
+    pub fn new(
+        from: &Account,
+        to: &Account,
+        amount: &Money,
+        response: &TransferResponse,
+        message: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            payment_id: response.payment_id.clone(),
+            status: response.status.clone(),
+            from_account: from.account_number.clone(),
+            from_account_name: from.name.clone(),
+            to_account: to.account_number.clone(),
+            to_account_name: to.name.clone(),
+            amount: amount.amount,
+            currency_code: amount.currency.clone(),
+            message,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Account, Transaction};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountColumn {
+    Name,
+    Balance,
+    Type,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionColumn {
+    Date,
+    Description,
+    Amount,
+}
+
+// No column means the order the bank returned
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SortOrder<C> {
+    pub column: Option<C>,
+    pub descending: bool,
+}
+
+impl<C> Default for SortOrder<C> {
+    fn default() -> Self {
+        Self {
+            column: None,
+            descending: false,
+        }
+    }
+}
+
+impl<C: Copy + PartialEq> SortOrder<C> {
+    // Ascending, then descending, then back to the bank's order
+    pub fn toggle(&mut self, column: C) {
+        *self = match (self.column, self.descending) {
+            (Some(current), false) if current == column => Self {
+                column: Some(column),
+                descending: true,
+            },
+            (Some(current), true) if current == column => Self::default(),
+            _ => Self {
+                column: Some(column),
+                descending: false,
+            },
+        };
+    }
+
+    // Arrow appended to the header of the sorted column
+    pub fn indicator(&self, column: C) -> &'static str {
+        match self.column {
+            Some(current) if current == column && self.descending => " ▼",
+            Some(current) if current == column => " ▲",
+            _ => "",
+        }
+    }
+}
+
+// Saved between sessions in the data directory
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct SortSettings {
+    pub accounts: SortOrder<AccountColumn>,
+    pub transactions: SortOrder<TransactionColumn>,
+}
+
+// `bank_order` holds the account keys as the bank listed them, for when no column is chosen
+pub fn sort_accounts(accounts: &mut [Account], order: &SortOrder<AccountColumn>, bank_order: &[String]) {
+    let position = |account: &Account| bank_order.iter().position(|key| key == &account.key);
+
+    accounts.sort_by(|a, b| {
+        let ordering = match order.column {
+            None => return position(a).cmp(&position(b)),
+            Some(AccountColumn::Name) => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Some(AccountColumn::Balance) => a.balance.cmp(&b.balance),
+            Some(AccountColumn::Type) => a.type_field.cmp(&b.type_field),
+        };
+        let ordering = if order.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| position(a).cmp(&position(b)))
+    });
+}
+
+// The bank lists transactions newest first, which is also the order without a column
+pub fn sort_transactions(transactions: &mut [Transaction], order: &SortOrder<TransactionColumn>) {
+    transactions.sort_by(|a, b| {
+        let ordering = match order.column {
+            None => return b.date.cmp(&a.date),
+            Some(TransactionColumn::Date) => a.date.cmp(&b.date),
+            Some(TransactionColumn::Description) => description(a).cmp(&description(b)),
+            Some(TransactionColumn::Amount) => a.amount.cmp(&b.amount),
+        };
+        let ordering = if order.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| b.date.cmp(&a.date))
+    });
+}
+
+fn description(tx: &Transaction) -> String {
+    tx.display_description()
+        .map(str::to_lowercase)
+        .unwrap_or_default()
+}
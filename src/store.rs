@@ -0,0 +1,298 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use log::debug;
+use rusqlite::{params, Connection};
+
+use crate::{
+    bank::DateRange,
+    error::StoreError,
+    models::{Account, Transaction},
+};
+
+// Accounts and transactions are kept as the JSON the bank returned, with the columns needed for
+// lookups and sync bookkeeping alongside
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS accounts (
+    key TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    id TEXT PRIMARY KEY,
+    account_key TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    booking_status TEXT NOT NULL,
+    data TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    booked_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS transactions_account ON transactions (account_key, date);
+";
+
+pub struct Store {
+    conn: Connection,
+}
+
+#[derive(Default, Debug)]
+pub struct SyncSummary {
+    pub added: usize,
+    // Pending transactions that have since been booked
+    pub booked: usize,
+    // Pending transactions the bank no longer returns
+    pub removed: usize,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        debug!("Opening local database at {}", path.display());
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StoreError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn accounts(&self) -> Result<Vec<Account>, StoreError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM accounts ORDER BY position")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut accounts = Vec::new();
+        for data in rows {
+            accounts.push(serde_json::from_str(&data?)?);
+        }
+        Ok(accounts)
+    }
+
+    // When the saved accounts were last fetched from the bank, in milliseconds since the epoch
+    pub fn accounts_updated_at(&self) -> Result<Option<i64>, StoreError> {
+        Ok(self
+            .conn
+            .query_row("SELECT MAX(updated_at) FROM accounts", [], |row| row.get(0))?)
+    }
+
+    // Replaces the saved accounts, keeping the order the bank returned them in
+    pub fn save_accounts(&mut self, accounts: &[Account]) -> Result<(), StoreError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM accounts", [])?;
+        for (position, account) in accounts.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO accounts (key, position, data, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    account.key,
+                    position as i64,
+                    serde_json::to_string(account)?,
+                    now
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Newest first, like the bank's own listing
+    pub fn transactions(&self, account_key: &str) -> Result<Vec<Transaction>, StoreError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT data FROM transactions WHERE account_key = ?1 ORDER BY date DESC, rowid",
+        )?;
+        let rows = stmt.query_map([account_key], |row| row.get::<_, String>(0))?;
+
+        let mut transactions = Vec::new();
+        for data in rows {
+            transactions.push(serde_json::from_str(&data?)?);
+        }
+        Ok(transactions)
+    }
+
+    // Merges transactions fetched for `range` into the store. Booked transactions are kept even once
+    // the bank stops returning them, which is how history builds up beyond the API's window.
+    pub fn sync_transactions(
+        &mut self,
+        account_key: &str,
+        range: &DateRange,
+        fetched: &[Transaction],
+    ) -> Result<SyncSummary, StoreError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut summary = SyncSummary::default();
+        let tx = self.conn.transaction()?;
+
+        let known: HashMap<String, (String, i64)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, booking_status, date FROM transactions WHERE account_key = ?1",
+            )?;
+            stmt.query_map([account_key], |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+            })?
+            .collect::<Result<_, _>>()?
+        };
+
+        for transaction in fetched {
+            let data = serde_json::to_string(transaction)?;
+            let pending = is_pending(&transaction.booking_status);
+
+            match known.get(&transaction.id).map(|(status, _)| status) {
+                None => {
+                    tx.execute(
+                        "INSERT INTO transactions
+                            (id, account_key, date, booking_status, data, first_seen, booked_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            transaction.id,
+                            account_key,
+                            transaction.date,
+                            transaction.booking_status,
+                            data,
+                            now,
+                            (!pending).then_some(now)
+                        ],
+                    )?;
+                    summary.added += 1;
+                }
+                Some(status) => {
+                    if is_pending(status) && !pending {
+                        tx.execute(
+                            "UPDATE transactions SET booked_at = ?2 WHERE id = ?1",
+                            params![transaction.id, now],
+                        )?;
+                        summary.booked += 1;
+                    }
+                    tx.execute(
+                        "UPDATE transactions SET date = ?2, booking_status = ?3, data = ?4 WHERE id = ?1",
+                        params![
+                            transaction.id,
+                            transaction.date,
+                            transaction.booking_status,
+                            data
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        // A pending transaction that disappears from the fetched range has either been cancelled or
+        // booked under a new id
+        for (id, (status, date)) in &known {
+            if is_pending(status) && range.contains(*date) && !fetched.iter().any(|t| &t.id == id) {
+                tx.execute("DELETE FROM transactions WHERE id = ?1", [id])?;
+                summary.removed += 1;
+            }
+        }
+
+        tx.commit()?;
+        debug!("Synced transactions for {}: {:?}", account_key, summary);
+        Ok(summary)
+    }
+}
+
+fn is_pending(booking_status: &str) -> bool {
+    booking_status.eq_ignore_ascii_case("PENDING")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    // Noon UTC, so the booking date is the same in every local time zone
+    fn transaction(id: &str, day: u32, status: &str) -> Transaction {
+        let date = NaiveDate::from_ymd_opt(2025, 3, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis();
+        Transaction {
+            id: id.to_string(),
+            date,
+            booking_status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(store: &Store) -> Vec<String> {
+        store
+            .transactions("acc")
+            .unwrap()
+            .into_iter()
+            .map(|tx| tx.id)
+            .collect()
+    }
+
+    #[test]
+    fn inserts_new_transactions() {
+        let mut store = Store::open_in_memory().unwrap();
+        let fetched = [
+            transaction("a", 1, "BOOKED"),
+            transaction("b", 2, "PENDING"),
+        ];
+
+        let summary = store
+            .sync_transactions("acc", &DateRange::default(), &fetched)
+            .unwrap();
+        assert_eq!((summary.added, summary.booked, summary.removed), (2, 0, 0));
+        assert_eq!(ids(&store), ["b", "a"]);
+
+        let summary = store
+            .sync_transactions("acc", &DateRange::default(), &fetched)
+            .unwrap();
+        assert_eq!((summary.added, summary.booked, summary.removed), (0, 0, 0));
+        assert_eq!(ids(&store), ["b", "a"]);
+    }
+
+    #[test]
+    fn counts_pending_transactions_once_booked() {
+        let mut store = Store::open_in_memory().unwrap();
+        let range = DateRange::default();
+        store
+            .sync_transactions("acc", &range, &[transaction("a", 1, "PENDING")])
+            .unwrap();
+
+        let summary = store
+            .sync_transactions("acc", &range, &[transaction("a", 3, "BOOKED")])
+            .unwrap();
+        assert_eq!((summary.added, summary.booked, summary.removed), (0, 1, 0));
+
+        let saved = store.transactions("acc").unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].booking_status, "BOOKED");
+        assert_eq!(saved[0].date, transaction("a", 3, "BOOKED").date);
+    }
+
+    #[test]
+    fn removes_pending_transactions_gone_from_the_range() {
+        let mut store = Store::open_in_memory().unwrap();
+        store
+            .sync_transactions(
+                "acc",
+                &DateRange::default(),
+                &[
+                    transaction("old", 1, "PENDING"),
+                    transaction("gone", 10, "PENDING"),
+                    transaction("kept", 11, "BOOKED"),
+                ],
+            )
+            .unwrap();
+
+        // The booked transaction is no longer returned either, but only pending ones are dropped,
+        // and only inside the fetched range
+        let range = DateRange {
+            from: NaiveDate::from_ymd_opt(2025, 3, 5),
+            to: NaiveDate::from_ymd_opt(2025, 3, 31),
+        };
+        let summary = store.sync_transactions("acc", &range, &[]).unwrap();
+        assert_eq!((summary.added, summary.booked, summary.removed), (0, 0, 1));
+        assert_eq!(ids(&store), ["kept", "old"]);
+    }
+}
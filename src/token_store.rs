@@ -0,0 +1,377 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce, aead::Aead};
+use log::debug;
+
+use crate::{
+    error::TokenStoreError,
+    fileio::{self, TokenStorage},
+    models::TokenData,
+};
+
+const KEYRING_SERVICE: &str = "auox";
+const KEYRING_USER: &str = "oauth-token";
+const PASSPHRASE_ENV: &str = "AUOX_PASSPHRASE";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Result<Option<TokenData>, TokenStoreError>;
+    fn save(&self, token_data: &TokenData) -> Result<(), TokenStoreError>;
+}
+
+// Plaintext auth.json, readable only by the owner
+pub struct PlainFile {
+    path: PathBuf,
+}
+
+impl PlainFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl TokenStore for PlainFile {
+    fn load(&self) -> Result<Option<TokenData>, TokenStoreError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // Older versions wrote the file with default permissions
+        restrict_permissions(&self.path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    fn save(&self, token_data: &TokenData) -> Result<(), TokenStoreError> {
+        let json = serde_json::to_string_pretty(token_data)?;
+        write_private(&self.path, json.as_bytes())?;
+        debug!("Token data saved to {}", self.path.display());
+        Ok(())
+    }
+}
+
+// The system keyring: Secret Service on Linux, Keychain on macOS, Credential Manager on Windows
+pub struct Keyring {
+    entry: keyring::Entry,
+}
+
+impl Keyring {
+    pub fn new() -> Result<Self, TokenStoreError> {
+        Ok(Self {
+            entry: keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?,
+        })
+    }
+}
+
+impl TokenStore for Keyring {
+    fn load(&self) -> Result<Option<TokenData>, TokenStoreError> {
+        match self.entry.get_password() {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, token_data: &TokenData) -> Result<(), TokenStoreError> {
+        self.entry
+            .set_password(&serde_json::to_string(token_data)?)?;
+        debug!("Token data saved to the keyring");
+        Ok(())
+    }
+}
+
+// auth.enc holds a random salt and nonce followed by the token JSON, encrypted with
+// ChaCha20-Poly1305 under a key derived from the passphrase with Argon2id
+pub struct EncryptedFile {
+    path: PathBuf,
+    passphrase: String,
+}
+
+impl EncryptedFile {
+    // Asks for the passphrase right away, twice if the file is about to be created
+    pub fn new(path: PathBuf) -> Result<Self, TokenStoreError> {
+        let passphrase = read_passphrase(!path.exists())?;
+        Ok(Self { path, passphrase })
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<ChaCha20Poly1305, TokenStoreError> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|_| TokenStoreError::Decrypt)?;
+        Ok(ChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+impl TokenStore for EncryptedFile {
+    fn load(&self) -> Result<Option<TokenData>, TokenStoreError> {
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        if content.len() < SALT_LEN + NONCE_LEN {
+            return Err(TokenStoreError::Decrypt);
+        }
+        let (salt, rest) = content.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let json = self
+            .cipher(salt)?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| TokenStoreError::Decrypt)?;
+        Ok(Some(serde_json::from_slice(&json)?))
+    }
+
+    fn save(&self, token_data: &TokenData) -> Result<(), TokenStoreError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::fill(&mut salt).map_err(io::Error::from)?;
+        getrandom::fill(&mut nonce).map_err(io::Error::from)?;
+
+        let json = serde_json::to_vec(token_data)?;
+        let ciphertext = self
+            .cipher(&salt)?
+            .encrypt(Nonce::from_slice(&nonce), json.as_slice())
+            .map_err(|_| TokenStoreError::Decrypt)?;
+
+        let mut content = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+        content.extend_from_slice(&salt);
+        content.extend_from_slice(&nonce);
+        content.extend_from_slice(&ciphertext);
+        write_private(&self.path, &content)?;
+        debug!("Encrypted token data saved to {}", self.path.display());
+        Ok(())
+    }
+}
+
+fn read_passphrase(confirm: bool) -> Result<String, TokenStoreError> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password("Passphrase for the auox tokens: ")?;
+    if passphrase.is_empty() {
+        return Err(TokenStoreError::Passphrase(
+            "The passphrase can't be empty".to_string(),
+        ));
+    }
+    if confirm && rpassword::prompt_password("Repeat the passphrase: ")? != passphrase {
+        return Err(TokenStoreError::Passphrase(
+            "The passphrases did not match".to_string(),
+        ));
+    }
+    Ok(passphrase)
+}
+
+fn write_private(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    // The mode only applies to new files, so an existing file is restricted before writing
+    let mut file = options.open(path)?;
+    restrict_permissions(path)?;
+    file.write_all(content)
+}
+
+fn restrict_permissions(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+struct Tokens {
+    store: Box<dyn TokenStore>,
+    cached: Mutex<Option<TokenData>>,
+}
+
+// Set once at startup. API calls read the cached tokens, so the keyring or passphrase is only
+// needed when loading and saving.
+static TOKENS: OnceLock<Tokens> = OnceLock::new();
+
+pub fn init(storage: TokenStorage) -> Result<(), TokenStoreError> {
+    debug!("Using {:?} token storage", storage);
+    let store: Box<dyn TokenStore> = match storage {
+        TokenStorage::File => Box::new(PlainFile::new(required_path(fileio::token_file_path())?)),
+        TokenStorage::Keyring => Box::new(Keyring::new()?),
+        TokenStorage::Encrypted => Box::new(EncryptedFile::new(required_path(
+            fileio::encrypted_token_file_path(),
+        )?)?),
+    };
+
+    let mut token_data = store.load()?;
+    if token_data.is_none() && storage != TokenStorage::File {
+        token_data = move_plain_file(store.as_ref())?;
+    }
+
+    let _ = TOKENS.set(Tokens {
+        store,
+        cached: Mutex::new(token_data),
+    });
+    Ok(())
+}
+
+// Takes over tokens from auth.json after switching storage, so no new login is needed and no
+// plaintext copy is left behind
+fn move_plain_file(store: &dyn TokenStore) -> Result<Option<TokenData>, TokenStoreError> {
+    let Some(path) = fileio::token_file_path() else {
+        return Ok(None);
+    };
+    let plain = PlainFile::new(path);
+    let Some(token_data) = plain.load()? else {
+        return Ok(None);
+    };
+
+    store.save(&token_data)?;
+    fs::remove_file(&plain.path)?;
+    debug!("Moved token data out of {}", plain.path.display());
+    Ok(Some(token_data))
+}
+
+fn required_path(path: Option<PathBuf>) -> Result<PathBuf, TokenStoreError> {
+    path.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "Could not determine data directory",
+        )
+        .into()
+    })
+}
+
+pub fn load() -> Option<TokenData> {
+    TOKENS
+        .get()?
+        .cached
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+// Reads the tokens from storage again, picking up a refresh made by another auox process
+pub fn reload() -> Option<TokenData> {
+    let tokens = TOKENS.get()?;
+    let mut cached = tokens
+        .cached
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    match tokens.store.load() {
+        Ok(token_data) => *cached = token_data,
+        Err(err) => debug!("Could not reload tokens, using the cached ones: {}", err),
+    }
+    cached.clone()
+}
+
+// The new tokens are used for the rest of the session even if they could not be saved
+pub fn save(token_data: &TokenData) -> Result<(), TokenStoreError> {
+    let tokens = TOKENS.get().expect("token store used before init");
+    *tokens
+        .cached
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(token_data.clone());
+    tokens.store.save(token_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_data() -> TokenData {
+        TokenData {
+            access_token: "access".to_string(),
+            expires_in: 600,
+            refresh_token_expires_in: 3600,
+            refresh_token_absolute_expires_in: 86400,
+            token_type: "Bearer".to_string(),
+            refresh_token: "refresh".to_string(),
+            issued_at: Some(1_700_000_000),
+        }
+    }
+
+    fn encrypted_file(name: &str, passphrase: &str) -> EncryptedFile {
+        let path = std::env::temp_dir()
+            .join(format!("auox-test-{}", std::process::id()))
+            .join(name);
+        EncryptedFile {
+            path,
+            passphrase: passphrase.to_string(),
+        }
+    }
+
+    #[test]
+    fn encrypted_file_round_trip() {
+        let store = encrypted_file("round-trip.enc", "correct horse");
+        store.save(&token_data()).unwrap();
+
+        let content = fs::read(&store.path).unwrap();
+        assert!(!content.windows(7).any(|window| window == b"refresh"));
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.access_token, "access");
+        assert_eq!(loaded.refresh_token, "refresh");
+        assert_eq!(loaded.issued_at, Some(1_700_000_000));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&store.path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(&store.path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_file_rejects_wrong_passphrase() {
+        let store = encrypted_file("wrong-passphrase.enc", "correct horse");
+        store.save(&token_data()).unwrap();
+
+        let other = encrypted_file("wrong-passphrase.enc", "battery staple");
+        assert!(matches!(other.load(), Err(TokenStoreError::Decrypt)));
+        fs::remove_file(&store.path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_file_rejects_damaged_content() {
+        let store = encrypted_file("damaged.enc", "correct horse");
+        store.save(&token_data()).unwrap();
+
+        let mut content = fs::read(&store.path).unwrap();
+        let last = content.len() - 1;
+        content[last] ^= 1;
+        fs::write(&store.path, &content).unwrap();
+        assert!(matches!(store.load(), Err(TokenStoreError::Decrypt)));
+
+        fs::write(&store.path, &content[..SALT_LEN]).unwrap();
+        assert!(matches!(store.load(), Err(TokenStoreError::Decrypt)));
+        fs::remove_file(&store.path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_file_missing_is_empty() {
+        let store = encrypted_file("missing.enc", "correct horse");
+        assert!(store.load().unwrap().is_none());
+    }
+}
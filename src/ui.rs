@@ -1,16 +1,24 @@
 use std::{io::Stdout, time::Duration};
 
+use chrono::{DateTime, Local};
+
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame, Terminal,
 };
 use tachyonfx::EffectManager;
 
-use crate::{AppState, View};
+use crate::{
+    amount::{AmountError, TransferError},
+    fileio,
+    models::Money,
+    sort::{AccountColumn, TransactionColumn},
+    AppState, TransferOutcome, View,
+};
 
 pub const MENU_ITEMS: &[(&str, &str, View)] = &[
     ("Transactions", "T", View::Transactions),
@@ -42,6 +50,10 @@ pub fn draw(
             Some(&View::Transactions) => {
                 draw_transactions_view(app, frame, frame_area, q_progress);
             }
+            Some(&View::TransactionDetail) => {
+                draw_transactions_view(app, frame, frame_area, q_progress);
+                draw_transaction_detail(app, frame, frame_area);
+            }
             Some(&View::TransferSelect) => {
                 draw_account_view(app, frame, frame_area, "Select target account", ARROW, q_progress);
             }
@@ -49,9 +61,21 @@ pub fn draw(
                 draw_account_view(app, frame, frame_area, "Select target account", ARROW, q_progress);
                 draw_transfer_modal(app, frame, frame_area);
             }
+            Some(&View::TransferConfirm) => {
+                draw_account_view(app, frame, frame_area, "Select target account", ARROW, q_progress);
+                draw_transfer_confirm(app, frame, frame_area);
+            }
+            Some(&View::TransferResult) => {
+                draw_account_view(app, frame, frame_area, "Select target account", ARROW, q_progress);
+                draw_transfer_result(app, frame, frame_area);
+            }
             None => {}
         }
 
+        if let Some(error) = &app.error {
+            draw_error_popup(&error.to_string(), frame, frame_area);
+        }
+
         effects.process_effects(elapsed.into(), frame.buffer_mut(), frame_area);
     });
 }
@@ -67,17 +91,24 @@ fn draw_account_view(
     let chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).split(frame_area);
 
     // Create header row
-    let header = Row::new(vec!["Account Name", "Balance", "Account Number", "Owner"]).style(
+    let sort = &app.sort.accounts;
+    let header = Row::new(vec![
+        format!("Account Name{}", sort.indicator(AccountColumn::Name)),
+        format!("Type{}", sort.indicator(AccountColumn::Type)),
+        format!("Balance{}", sort.indicator(AccountColumn::Balance)),
+        "Account Number".to_string(),
+        "Owner".to_string(),
+    ])
+    .style(
         Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD),
     );
 
     // Create table rows from accounts
-    let rows: Vec<Row> = app
-        .accounts
+    let visible = crate::visible_accounts(&app.accounts, app.show_credit_card);
+    let rows: Vec<Row> = visible
         .iter()
-        .filter(|acc| app.show_credit_card || acc.type_field != "CREDITCARD")
         .map(|acc| {
             let balance = if app.show_balance {
                 format!("{:.2}", acc.balance)
@@ -87,6 +118,7 @@ fn draw_account_view(
 
             Row::new(vec![
                 Cell::from(acc.name.as_str()),
+                Cell::from(account_type(&acc.type_field)),
                 Cell::from(balance),
                 Cell::from(acc.account_number.as_str()),
                 Cell::from(acc.owner.as_ref().map(|o| o.name.as_str()).unwrap_or("N/A")),
@@ -97,11 +129,29 @@ fn draw_account_view(
     // Define column widths
     let widths = [
         Constraint::Percentage(25),
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
+        Constraint::Percentage(15),
+        Constraint::Percentage(20),
+        Constraint::Percentage(20),
+        Constraint::Percentage(20),
     ];
 
+    // Show the summed balance of the visible accounts next to the title
+    let title = if app.show_balance {
+        let totals: Vec<String> = Money::totals(
+            visible.iter().map(|acc| acc.balance_money()),
+        )
+        .iter()
+        .map(|total| total.to_string())
+        .collect();
+        format!("{} | Total: {}", title, totals.join(", "))
+    } else {
+        title.to_string()
+    };
+    let title = match app.accounts_updated {
+        Some(updated) => format!("{} | Updated {}", title, format_updated(updated)),
+        None => title,
+    };
+
     // Create the Table widget
     let table = Table::new(rows, widths)
         .header(header)
@@ -117,8 +167,11 @@ fn draw_account_view(
     frame.render_stateful_widget(table, chunks[0], &mut app.account_index);
 
     // Help bar with commands
-    let help =
-        help_bar("Commands: [Ctrl+C] Quit | [esc] Back | [b] Toggle Balance | [↑/↓] Navigate", q_progress);
+    let help = status_bar(
+        app,
+        "Commands: [Ctrl+C] Quit | [esc] Back | [b] Toggle Balance | [r] Refresh | [1-3] Sort | [↑/↓] Navigate",
+        q_progress,
+    );
     frame.render_widget(help, chunks[1]);
 }
 
@@ -156,11 +209,13 @@ fn draw_transfer_modal(app: &mut AppState, frame: &mut Frame<'_>, frame_area: Re
 
     let inner_area = block.inner(block_area);
 
-    // Create vertical layout: first row for To/From, second row for Amount input, third row for Message input
+    // Create vertical layout: first row for To/From, second row for Amount input, third row for Message input,
+    // fourth row for validation errors
     let rows = Layout::vertical([
         Constraint::Length(1), // To/From labels
         Constraint::Length(3), // Amount input
         Constraint::Length(3), // Message input
+        Constraint::Length(1), // Validation error
     ])
     .split(inner_area);
 
@@ -169,13 +224,11 @@ fn draw_transfer_modal(app: &mut AppState, frame: &mut Frame<'_>, frame_area: Re
         Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).split(rows[0]);
 
     let from_name = app
-        .from_account
-        .and_then(|idx| app.accounts.get(idx))
+        .transfer_from()
         .map(|acc| acc.name.as_str())
         .unwrap_or("N/A");
     let to_name = app
-        .to_account
-        .and_then(|idx| app.accounts.get(idx))
+        .transfer_to()
         .map(|acc| acc.name.as_str())
         .unwrap_or("N/A");
 
@@ -235,6 +288,255 @@ fn draw_transfer_modal(app: &mut AppState, frame: &mut Frame<'_>, frame_area: Re
         .scroll((0, msg_scroll as u16));
 
     frame.render_widget(message_widget, message_chunks[1]);
+
+    // Fourth row: validation error, hidden until something has been typed in the amount field
+    match crate::validate_transfer_input(app) {
+        Some(Err(TransferError::Amount(AmountError::Empty))) | Some(Ok(_)) | None => {}
+        Some(Err(err)) => {
+            let error = Paragraph::new(err.to_string()).style(Style::default().fg(Color::Red));
+            frame.render_widget(error, rows[3]);
+        }
+    }
+}
+
+fn draw_transfer_confirm(app: &AppState, frame: &mut Frame<'_>, frame_area: Rect) {
+    let block_area = popup_area(frame_area, 60, 45);
+    let clear_area = popup_area(frame_area, 65, 50);
+
+    let (Some(from), Some(to)) = (app.transfer_from(), app.transfer_to()) else {
+        return;
+    };
+
+    let amount = match crate::validate_transfer_input(app) {
+        Some(Ok(amount)) => amount.to_string(),
+        _ => "Invalid amount".to_string(),
+    };
+
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Yellow));
+
+    let mut lines = vec![
+        Line::from(vec![
+            label("From:    "),
+            Span::raw(format!("{} ({})", from.name, from.account_number)),
+        ]),
+        Line::from(vec![
+            label("To:      "),
+            Span::raw(format!("{} ({})", to.name, to.account_number)),
+        ]),
+        Line::from(vec![label("Amount:  "), Span::raw(amount).bold()]),
+    ];
+
+    if to.type_field == "CREDITCARD" {
+        lines.push(Line::from(vec![
+            label("Card ID: "),
+            Span::raw(to.credit_card_account_id.as_deref().unwrap_or("N/A")),
+        ]));
+        lines.push(Line::from(vec![
+            label("Message: "),
+            Span::raw("Not supported for credit card transfers").dim(),
+        ]));
+    } else {
+        let message = app.message_input.value().trim();
+        lines.push(Line::from(vec![
+            label("Message: "),
+            Span::raw(if message.is_empty() { "-" } else { message }),
+        ]));
+    }
+
+    let mut actions = menu_text("Confirm", "y");
+    actions.push_span(Span::raw("   "));
+    actions.spans.extend(menu_text("Go back", "n/esc").spans);
+
+    lines.push(Line::default());
+    lines.push(actions);
+
+    let summary = Paragraph::new(lines)
+        .block(Block::bordered().title("Confirm transfer"))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, clear_area);
+    frame.render_widget(summary, block_area);
+}
+
+fn draw_transfer_result(app: &AppState, frame: &mut Frame<'_>, frame_area: Rect) {
+    let block_area = popup_area(frame_area, 60, 45);
+    let clear_area = popup_area(frame_area, 65, 50);
+
+    let Some(outcome) = &app.transfer_outcome else {
+        return;
+    };
+
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Yellow));
+
+    let (title, border_color, mut lines) = match outcome {
+        TransferOutcome::Sending => {
+            let lines = vec![
+                Line::from(app.worker.loading().unwrap_or_default()).bold(),
+                Line::default(),
+                Line::from("Waiting for the bank to confirm the transfer").dim(),
+            ];
+            let paragraph = Paragraph::new(lines).block(Block::bordered().title("Transfer"));
+            frame.render_widget(Clear, clear_area);
+            frame.render_widget(paragraph, block_area);
+            return;
+        }
+        TransferOutcome::Completed {
+            receipt,
+            receipt_saved,
+        } => {
+            let mut lines = vec![
+                Line::from("Transfer completed").green().bold(),
+                Line::default(),
+                Line::from(vec![
+                    label("Payment ID: "),
+                    Span::raw(receipt.payment_id.as_deref().unwrap_or("N/A")),
+                ]),
+                Line::from(vec![
+                    label("Status:     "),
+                    Span::raw(receipt.status.as_deref().unwrap_or("N/A")),
+                ]),
+                Line::from(vec![
+                    label("Amount:     "),
+                    Span::raw(format!("{:.2} {}", receipt.amount, receipt.currency_code)),
+                ]),
+                Line::from(vec![
+                    label("From:       "),
+                    Span::raw(receipt.from_account_name.as_str()),
+                ]),
+                Line::from(vec![
+                    label("To:         "),
+                    Span::raw(receipt.to_account_name.as_str()),
+                ]),
+                Line::default(),
+            ];
+            match receipt_saved {
+                Some(true) => lines.push(
+                    Line::from(match fileio::receipts_file_path() {
+                        Some(path) => format!("Receipt saved to {}", path.display()),
+                        None => "Receipt saved".to_string(),
+                    })
+                    .dim(),
+                ),
+                Some(false) => lines.push(Line::from("The receipt could not be saved").red()),
+                None => {}
+            }
+            ("Transfer complete", Color::Green, lines)
+        }
+        TransferOutcome::Failed { summary, errors } => {
+            let mut lines = vec![
+                Line::from("Transfer failed").red().bold(),
+                Line::from(summary.as_str()),
+                Line::default(),
+            ];
+            for error in errors {
+                let message = error
+                    .localized_message
+                    .as_ref()
+                    .and_then(|localized| localized.message.as_deref())
+                    .unwrap_or(&error.message);
+                lines.push(Line::from(format!("• {}", message)));
+                lines.push(Line::from(format!("  Trace ID: {}", error.trace_id)).dim());
+            }
+            ("Transfer failed", Color::Red, lines)
+        }
+    };
+
+    lines.push(Line::default());
+    lines.push(menu_text("Close", "enter/esc"));
+
+    let result = Paragraph::new(lines)
+        .block(
+            Block::bordered()
+                .title(title)
+                .border_style(Style::default().fg(border_color)),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, clear_area);
+    frame.render_widget(result, block_area);
+}
+
+fn draw_transaction_detail(app: &AppState, frame: &mut Frame<'_>, frame_area: Rect) {
+    let block_area = popup_area(frame_area, 60, 60);
+    let clear_area = popup_area(frame_area, 65, 65);
+
+    let Some(tx) = app.selected_transaction() else {
+        return;
+    };
+
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Yellow));
+    let field = |name: &'static str, value: String| Line::from(vec![label(name), Span::raw(value)]);
+    let or_na = |value: Option<&String>| value.cloned().unwrap_or_else(|| "N/A".to_string());
+
+    let amount = tx.money();
+    let amount_style = if amount.is_negative() {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default().fg(Color::Green)
+    };
+
+    let mut lines = vec![
+        Line::from(tx.display_description().unwrap_or("N/A")).bold(),
+        Line::default(),
+        Line::from(vec![
+            label("Amount:           "),
+            Span::styled(amount.to_string(), amount_style),
+        ]),
+        field("Date:             ", format_timestamp(tx.date)),
+        field(
+            "Interest date:    ",
+            tx.interest_date.map_or("N/A".to_string(), format_timestamp),
+        ),
+        field("Type:             ", format!("{} ({})", tx.type_text, tx.type_code)),
+        field("Status:           ", tx.booking_status.clone()),
+        field("Description:      ", or_na(tx.description.as_ref())),
+        Line::default(),
+        field("Remote account:   ", or_na(tx.remote_account_number.as_ref())),
+        field("Remote name:      ", or_na(tx.remote_account_name.as_ref())),
+        field("KID/message:      ", or_na(tx.kid_or_message.as_ref())),
+        Line::default(),
+        field("Account:          ", format!("{} ({})", tx.account_name, tx.account_number.formatted)),
+        field("Account currency: ", tx.account_currency.clone()),
+        field("Source:           ", tx.source.clone()),
+        field(
+            "Confidential:     ",
+            if tx.is_confidential { "Yes" } else { "No" }.to_string(),
+        ),
+        field("ID:               ", tx.id.clone()),
+    ];
+
+    let mut actions = menu_text("Copy account number", "a");
+    actions.push_span(Span::raw("   "));
+    actions.spans.extend(menu_text("Copy KID/message", "k").spans);
+    actions.push_span(Span::raw("   "));
+    actions.spans.extend(menu_text("Close", "enter/esc").spans);
+
+    lines.push(Line::default());
+    lines.push(actions);
+
+    let detail = Paragraph::new(lines)
+        .block(Block::bordered().title("Transaction details"))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, clear_area);
+    frame.render_widget(detail, block_area);
+}
+
+fn draw_error_popup(message: &str, frame: &mut Frame<'_>, frame_area: Rect) {
+    let block_area = popup_area(frame_area, 50, 25);
+    let clear_area = popup_area(frame_area, 55, 30);
+
+    let block = Block::bordered()
+        .title("Error")
+        .title_bottom(Line::from(" [enter/esc] Dismiss ").right_aligned())
+        .border_style(Style::default().fg(Color::Red));
+
+    let text = Paragraph::new(message)
+        .block(block)
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(Clear, clear_area);
+    frame.render_widget(text, block_area);
 }
 
 fn draw_transactions_view(app: &mut AppState, frame: &mut Frame<'_>, frame_area: Rect, q_progress: Option<f32>) {
@@ -242,29 +544,43 @@ fn draw_transactions_view(app: &mut AppState, frame: &mut Frame<'_>, frame_area:
     let chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).split(frame_area);
 
     // Create header row
-    let header = Row::new(vec!["Date", "Description", "Amount", "Type"]).style(
+    let sort = &app.sort.transactions;
+    let header = Row::new(vec![
+        format!("Date{}", sort.indicator(TransactionColumn::Date)),
+        format!("Description{}", sort.indicator(TransactionColumn::Description)),
+        format!("Amount{}", sort.indicator(TransactionColumn::Amount)),
+        "Type".to_string(),
+    ])
+    .style(
         Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD),
     );
 
-    // Create table rows from transactions
-    let rows: Vec<Row> = app
-        .transactions
-        .iter()
+    // Create table rows from the transactions left after search and filters
+    let visible = app.filter.apply(&app.transactions);
+    let title = if app.filter.is_active() {
+        format!(
+            "Transactions ({} of {}) | {}",
+            visible.len(),
+            app.transactions.len(),
+            app.filter.describe(&app.transactions)
+        )
+    } else {
+        format!("Transactions ({})", app.transactions.len())
+    };
+
+    let rows: Vec<Row> = visible
+        .into_iter()
         .map(|tx| {
             let date_str = format_timestamp(tx.date);
 
-            let desc = tx
-                .cleaned_description
-                .as_ref()
-                .or(tx.description.as_ref())
-                .map(|s| s.as_str())
-                .unwrap_or("N/A");
+            let desc = tx.display_description().unwrap_or("N/A");
 
-            let amount_str = format!("{:.2} {}", tx.amount, tx.currency_code);
+            let amount = tx.money();
+            let amount_str = amount.to_string();
 
-            let amount_cell = if tx.amount >= 0.0 {
+            let amount_cell = if !amount.is_negative() {
                 Cell::from(amount_str).style(Style::default().fg(Color::Green))
             } else {
                 Cell::from(amount_str).style(Style::default().fg(Color::Red))
@@ -288,7 +604,7 @@ fn draw_transactions_view(app: &mut AppState, frame: &mut Frame<'_>, frame_area:
 
     let table = Table::new(rows, widths)
         .header(header)
-        .block(Block::default().borders(Borders::ALL).title("Transactions"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .row_highlight_style(
             Style::default()
                 .bg(Color::Blue)
@@ -300,8 +616,23 @@ fn draw_transactions_view(app: &mut AppState, frame: &mut Frame<'_>, frame_area:
     frame.render_widget(Clear, frame_area);
     frame.render_stateful_widget(table, chunks[0], &mut app.transaction_index);
 
-    let help = help_bar("Commands: [Ctrl+C] Quit | [esc] Back | [↑/↓] Navigate", q_progress);
-    frame.render_widget(help, chunks[1]);
+    if app.searching {
+        let search = Paragraph::new(app.search_input.value())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Search | amounts: >500, <100, 100..500 | [enter] Keep | [esc] Clear"),
+            )
+            .style(Style::default().fg(Color::Yellow));
+        frame.render_widget(search, chunks[1]);
+    } else {
+        let help = status_bar(
+            app,
+            "Commands: [Ctrl+C] Quit | [esc] Back | [/] Search | [i/t/s] Filter | [x] Clear | [1-3] Sort | [r] Refresh | [l] Earlier | [e/o] Export",
+            q_progress,
+        );
+        frame.render_widget(help, chunks[1]);
+    }
 }
 
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
@@ -322,7 +653,16 @@ fn menu_text<'a>(option: &'a str, shortcut: &'a str) -> ratatui::prelude::Line<'
 }
 
 
-fn help_bar(text: &str, q_progress: Option<f32>) -> Paragraph<'_> {
+// A running bank call takes precedence over a one-off status message, which replaces the commands
+fn status_bar(app: &AppState, commands: &str, q_progress: Option<f32>) -> Paragraph<'static> {
+    match (app.worker.loading(), &app.status) {
+        (Some(loading), _) => help_bar(&loading, q_progress),
+        (None, Some(status)) => help_bar(status, q_progress),
+        (None, None) => help_bar(commands, q_progress),
+    }
+}
+
+fn help_bar(text: &str, q_progress: Option<f32>) -> Paragraph<'static> {
     let display_text = if let Some(progress) = q_progress {
         let bar_width = 20;
         let filled = (bar_width as f32 * progress) as usize;
@@ -337,7 +677,25 @@ fn help_bar(text: &str, q_progress: Option<f32>) -> Paragraph<'_> {
         .style(Style::default().fg(Color::Cyan))
 }
 
-fn format_timestamp(timestamp_ms: i64) -> String {
+fn account_type(type_field: &str) -> &str {
+    match type_field {
+        "CURRENT" => "Current",
+        "SAVINGS" => "Savings",
+        "CREDITCARD" => "Credit card",
+        other => other,
+    }
+}
+
+// Just the time for today, so it's easy to see how fresh the balances are
+fn format_updated(updated: DateTime<Local>) -> String {
+    if updated.date_naive() == Local::now().date_naive() {
+        updated.format("%H:%M:%S").to_string()
+    } else {
+        updated.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+pub fn format_timestamp(timestamp_ms: i64) -> String {
     use chrono::{DateTime, Local};
 
     let timestamp_secs = timestamp_ms / 1000;
@@ -0,0 +1,144 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+use log::debug;
+
+use crate::{
+    bank::{BankProvider, DateRange},
+    error::ApiError,
+    models::{Account, Money, Transaction, TransferResponse},
+};
+
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+// Result of a bank call made on the worker, handed back to the event loop
+pub enum Loaded {
+    Accounts(Result<Vec<Account>, ApiError>),
+    Transactions {
+        account_key: String,
+        range: DateRange,
+        result: Result<Vec<Transaction>, ApiError>,
+    },
+    Transfer {
+        request: Box<TransferRequest>,
+        result: Result<TransferResponse, ApiError>,
+    },
+}
+
+pub struct TransferRequest {
+    pub from: Account,
+    pub to: Account,
+    pub amount: Money,
+    pub message: Option<String>,
+}
+
+impl Loaded {
+    fn label(&self) -> &'static str {
+        match self {
+            Loaded::Accounts(_) => ACCOUNTS,
+            Loaded::Transactions { .. } => TRANSACTIONS,
+            Loaded::Transfer { .. } => TRANSFER,
+        }
+    }
+}
+
+const ACCOUNTS: &str = "Loading accounts";
+const TRANSACTIONS: &str = "Loading transactions";
+const TRANSFER: &str = "Sending transfer";
+
+// Runs blocking bank calls on background threads so drawing and animations never wait on the
+// network. Each call gets its own thread and reports back over a channel that the event loop
+// drains every frame.
+pub struct Worker {
+    bank: Arc<dyn BankProvider>,
+    sender: Sender<Loaded>,
+    receiver: Receiver<Loaded>,
+    // Labels of the calls still in flight, shown next to the spinner
+    running: Vec<&'static str>,
+    started: Instant,
+}
+
+impl Worker {
+    pub fn new(bank: Arc<dyn BankProvider>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            bank,
+            sender,
+            receiver,
+            running: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn accounts(&mut self) {
+        debug!("Fetching accounts");
+        self.spawn(ACCOUNTS, |bank| Loaded::Accounts(bank.accounts()));
+    }
+
+    pub fn transactions(&mut self, account_key: String, range: DateRange) {
+        debug!("Fetching transactions for {} in {:?}", account_key, range);
+        self.spawn(TRANSACTIONS, move |bank| {
+            let result = bank.transactions(&account_key, &range);
+            Loaded::Transactions {
+                account_key,
+                range,
+                result,
+            }
+        });
+    }
+
+    pub fn transfer(&mut self, request: TransferRequest) {
+        debug!("Sending transfer from {} to {}", request.from.name, request.to.name);
+        self.spawn(TRANSFER, move |bank| {
+            let result = bank.transfer(
+                &request.from,
+                &request.to,
+                request.amount.amount,
+                request.message.clone(),
+            );
+            Loaded::Transfer {
+                request: Box::new(request),
+                result,
+            }
+        });
+    }
+
+    // Next finished call, if any, without blocking
+    pub fn try_recv(&mut self) -> Option<Loaded> {
+        let loaded = self.receiver.try_recv().ok()?;
+        if let Some(idx) = self.running.iter().position(|label| *label == loaded.label()) {
+            self.running.remove(idx);
+        }
+        Some(loaded)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    // Spinner and description of the latest call still running
+    pub fn loading(&self) -> Option<String> {
+        let label = self.running.last()?;
+        let frame = (self.started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+        Some(format!("{} {}...", SPINNER[frame], label))
+    }
+
+    fn spawn<F>(&mut self, label: &'static str, call: F)
+    where
+        F: FnOnce(&dyn BankProvider) -> Loaded + Send + 'static,
+    {
+        let bank = Arc::clone(&self.bank);
+        let sender = self.sender.clone();
+        self.running.push(label);
+        thread::spawn(move || {
+            // The receiver only goes away when the app is exiting
+            let _ = sender.send(call(bank.as_ref()));
+        });
+    }
+}